@@ -16,6 +16,11 @@ dependencies {
     implementation("com.runemate:runemate-client:3.1.5.0:all")
     implementation("com.runemate:runemate-game-api:1.2.4")
     implementation(files("../core/src"))
+    testImplementation(kotlin("test"))
+}
+
+tasks.test {
+    useJUnitPlatform()
 }
 
 tasks.register<Copy>("buildRuneMateTestBot") {
@@ -2,7 +2,9 @@ package de.torsm.osrsnav
 
 import com.google.gson.FieldNamingPolicy
 import com.google.gson.GsonBuilder
+import com.google.gson.JsonElement
 import com.google.gson.TypeAdapter
+import com.google.gson.annotations.SerializedName
 import com.google.gson.reflect.TypeToken
 import com.google.gson.stream.JsonReader
 import com.google.gson.stream.JsonToken
@@ -12,6 +14,8 @@ import com.runemate.game.api.hybrid.entities.definitions.GameObjectDefinition
 import com.runemate.game.api.hybrid.local.Skill
 import com.runemate.game.api.hybrid.local.Varbits
 import com.runemate.game.api.hybrid.local.Varps
+import com.runemate.game.api.hybrid.local.Worlds
+import com.runemate.game.api.hybrid.local.hud.interfaces.Bank
 import com.runemate.game.api.hybrid.local.hud.interfaces.Equipment
 import com.runemate.game.api.hybrid.local.hud.interfaces.Inventory
 import com.runemate.game.api.hybrid.local.hud.interfaces.SpriteItem
@@ -37,7 +41,7 @@ object OsrsNav {
         HttpClient.newBuilder().build()
     }
 
-    private val gson by lazy {
+    internal val gson by lazy {
         val edgeTypeAdapterFactory = RuntimeTypeAdapterFactory.of(Edge::class.java)
             .registerSubtype(Step::class.java)
             .registerSubtype(Door::class.java)
@@ -66,20 +70,74 @@ object OsrsNav {
             }
         }
 
-    private fun doRequest(request: PathGenerationRequest): List<Edge>? {
+    private fun doRequest(request: Any): Path? {
         val json = gson.toJson(request)
         val httpRequest = HttpRequest.newBuilder()
             .uri(URI("$NAV_URL/path"))
             .POST(HttpRequest.BodyPublishers.ofString(json))
             .build()
-        return doHttpRequest(httpRequest, TypeToken.getParameterized(List::class.java, Edge::class.java).type)
+        return doHttpRequest<Path>(httpRequest)
     }
 
-    fun buildBetween(start: Coordinate, end: Coordinate, gameState: GameState = GameState.fromGame()): List<Edge>? {
-        val request = PathGenerationRequest(start, end, gameState)
+    fun buildRoute(waypoints: List<Coordinate>, gameState: GameState = GameState.fromGame()): Route? {
+        val json = gson.toJson(RouteGenerationRequest(waypoints, gameState))
+        val httpRequest = HttpRequest.newBuilder()
+            .uri(URI("$NAV_URL/route"))
+            .POST(HttpRequest.BodyPublishers.ofString(json))
+            .build()
+        return doHttpRequest<Route>(httpRequest)
+    }
+
+    fun buildBetween(
+        start: Coordinate,
+        end: Coordinate,
+        gameState: GameState = GameState.fromGame(),
+        compress: Int? = null,
+        compression: Compression = Compression.LINE,
+        profile: String? = null,
+    ): Path? {
+        val request = PathGenerationRequest(start, end, gameState, compress, compression, profile)
         return doRequest(request)
     }
 
+    fun buildToNearest(start: Coordinate, ends: Collection<Coordinate>, gameState: GameState = GameState.fromGame()): Path? {
+        val request = NearestPathGenerationRequest(start, ends.toList(), gameState)
+        return doRequest(request)
+    }
+
+    fun buildAlternatives(
+        start: Coordinate,
+        end: Coordinate,
+        count: Int = 3,
+        maxOverlap: Double = 0.5,
+        gameState: GameState = GameState.fromGame(),
+    ): List<Path>? {
+        val json = gson.toJson(AlternativesRequest(start, end, count, maxOverlap, gameState))
+        val httpRequest = HttpRequest.newBuilder()
+            .uri(URI("$NAV_URL/alternatives"))
+            .POST(HttpRequest.BodyPublishers.ofString(json))
+            .build()
+        return doHttpRequest<List<Path>>(httpRequest, object : TypeToken<List<Path>>() {}.type)
+    }
+
+    fun buildPlan(start: Coordinate, stops: List<Coordinate>, end: Coordinate? = null, gameState: GameState = GameState.fromGame()): Plan? {
+        val json = gson.toJson(PlanRequest(start, stops, end, gameState))
+        val httpRequest = HttpRequest.newBuilder()
+            .uri(URI("$NAV_URL/plan"))
+            .POST(HttpRequest.BodyPublishers.ofString(json))
+            .build()
+        return doHttpRequest<Plan>(httpRequest)
+    }
+
+    fun buildMatrix(origins: List<Coordinate>, targets: List<Coordinate>, gameState: GameState = GameState.fromGame()): CostMatrix? {
+        val json = gson.toJson(MatrixRequest(origins, targets, gameState))
+        val httpRequest = HttpRequest.newBuilder()
+            .uri(URI("$NAV_URL/matrix"))
+            .POST(HttpRequest.BodyPublishers.ofString(json))
+            .build()
+        return doHttpRequest<CostMatrix>(httpRequest)
+    }
+
     private inline fun <reified T> doHttpRequest(request: HttpRequest, type: Type = T::class.java): T? = try {
         val httpResponse = httpClient.send(request, HttpResponse.BodyHandlers.ofInputStream())
         if (httpResponse.statusCode() == 200) {
@@ -100,8 +158,122 @@ data class PathGenerationRequest(
     val start: Coordinate,
     val end: Coordinate,
     val gameState: GameState,
+    /** Merges walks into waypoints at most this many tiles apart */
+    val compress: Int? = null,
+    val compression: Compression = Compression.LINE,
+    /** Name of a cost profile the server was started with */
+    val profile: String? = null,
+)
+
+data class NearestPathGenerationRequest(
+    val start: Coordinate,
+    val end: List<Coordinate>,
+    val gameState: GameState,
+)
+
+data class RouteGenerationRequest(
+    val waypoints: List<Coordinate>,
+    val gameState: GameState,
+)
+
+data class AlternativesRequest(
+    val start: Coordinate,
+    val end: Coordinate,
+    /** Most paths to return */
+    val count: Int,
+    /** Highest fraction of tiles a path may share with each other path */
+    val maxOverlap: Double,
+    val gameState: GameState,
+)
+
+data class PlanRequest(
+    val start: Coordinate,
+    val stops: List<Coordinate>,
+    val end: Coordinate?,
+    val gameState: GameState,
+)
+
+/** Indices of the stops in visiting order and the route through them */
+data class Plan(
+    val order: List<Int>,
+    val route: Route,
+)
+
+data class MatrixRequest(
+    val origins: List<Coordinate>,
+    val targets: List<Coordinate>,
+    val gameState: GameState,
+)
+
+/** Costs by origin and target, null if unreachable or beyond a limit */
+data class CostMatrix(
+    val costs: List<List<Int?>>,
+    val stopped: List<String?>,
+)
+
+data class Route(
+    val path: Path,
+    val legs: List<Leg>,
+)
+
+data class Leg(
+    val start: Coordinate,
+    val end: Coordinate,
+    /** Steps `start until end` of the route's path */
+    val steps: StepRange,
+    val cost: Int,
+)
+
+data class StepRange(
+    val start: Int,
+    val end: Int,
 )
 
+data class Path(
+    val start: Coordinate,
+    val end: Coordinate,
+    val cost: Int,
+    val steps: List<PathStep>,
+    val segments: List<Segment>,
+)
+
+data class PathStep(
+    val position: Coordinate,
+    /** Cost from the start up to and including this step */
+    val cost: Int,
+    val edge: PathEdge,
+)
+
+data class PathEdge(
+    val destination: Coordinate,
+    /** Cost the step took, rescaled by zones and cost profiles like the step's cost */
+    val cost: Int,
+    val definition: Edge,
+    /** Serialized requirement definitions, either an object or a plain string for those without fields */
+    val requirements: List<JsonElement>,
+)
+
+/** Steps `start until end` of the path, either a walk or a single action */
+data class Segment(
+    val kind: SegmentKind,
+    val start: Int,
+    val end: Int,
+    val cost: Int,
+)
+
+/** How compressed waypoints are chosen */
+enum class Compression {
+    /** The straight line from the previous waypoint is walkable */
+    @SerializedName("Line") LINE,
+    /** The client's route finder walks the path from the previous waypoint */
+    @SerializedName("Client") CLIENT,
+}
+
+enum class SegmentKind {
+    @SerializedName("Walk") WALK,
+    @SerializedName("Action") ACTION,
+}
+
 object PatternTypeAdapter : TypeAdapter<Pattern>() {
     override fun write(writer: JsonWriter, pattern: Pattern?) {
         if (pattern == null) {
@@ -128,13 +300,19 @@ data class DataSelection(
     val varbits: List<Int>,
     val items: List<Pattern>,
     val skills: List<String>,
+    val bank: Boolean,
 )
 
 data class GameState(
     val varps: Map<Int, Int>,
     val varbits: Map<Int, Int>,
-    val items: Map<String, Int>,
+    val inventory: Map<String, Int>,
+    val equipment: Map<String, Int>,
+    /** Left out unless the server asks for it, or while the bank hasn't been loaded this session */
+    val bank: Map<String, Int>?,
     val skills: Map<String, Int>,
+    /** Names of the current world's types, `MEMBERS` being the only source of membership */
+    val worldTypes: List<String>,
 ) {
     companion object {
         fun fromGame(): GameState {
@@ -146,12 +324,15 @@ data class GameState(
                 varbits.asSequence().mapNotNull { Varbits.load(it) }.associate { it.id to it.value }
             } ?: mapOf()
 
-            val spriteItems = OsrsNav.dataSelection?.items?.let { items ->
-                Inventory.newQuery().names(items).results().asList() + Equipment.newQuery().names(items).results().asList()
-            } ?: let {
-                Inventory.newQuery().results().asList() + Equipment.newQuery().results().asList()
-            }
-            val items = spriteItems.associate { (it.definition?.name ?: "null") to it.quantity }
+            val items = OsrsNav.dataSelection?.items
+            val inventoryItems = items?.let { Inventory.newQuery().names(it) } ?: Inventory.newQuery()
+            val equipmentItems = items?.let { Equipment.newQuery().names(it) } ?: Equipment.newQuery()
+            val inventory = inventoryItems.results().asList().associate { (it.definition?.name ?: "null") to it.quantity }
+            val equipment = equipmentItems.results().asList().associate { (it.definition?.name ?: "null") to it.quantity }
+            val bank = if (OsrsNav.dataSelection?.bank == true) {
+                val bankItems = items?.let { Bank.newQuery().names(it) } ?: Bank.newQuery()
+                bankItems.results().asList().associate { (it.definition?.name ?: "null") to it.quantity }.ifEmpty { null }
+            } else null
 
             val skills = OsrsNav.dataSelection?.skills?.let { skills ->
                 skills.associateWith { Skill.valueOf(it).currentLevel }
@@ -159,20 +340,25 @@ data class GameState(
                 Skill.values().asSequence().filter { it.currentLevel >= 0 }.associate { it.name to it.currentLevel }
             }
 
-            return GameState(varps, varbits, items, skills)
+            val worldTypes = Worlds.getOverview(Worlds.getCurrent())?.worldTypes?.map { it.name } ?: listOf()
+
+            return GameState(varps, varbits, inventory, equipment, bank, skills, worldTypes)
         }
     }
 }
 
-fun convert(path: List<Edge>): WebPath {
-    val mapped = path.map {
+fun convert(path: Path): WebPath {
+    val mapped = path.steps.map { it.edge.definition }.map {
         when (it) {
             is Door -> BasicObjectVertex(it.position, GameObjectDefinition.get(it.id)?.name, it.action, listOf())
             is GameObjectEdge -> BasicObjectVertex(it.position, GameObjectDefinition.get(it.id)?.name, it.action, listOf())
-            is ItemTeleport -> BasicItemTeleportVertex(Coordinate(0), SpriteItem.Origin.INVENTORY, it.item, it.action, listOf())
+            is ItemTeleport -> {
+                val origin = if (it.equipped) SpriteItem.Origin.EQUIPMENT else SpriteItem.Origin.INVENTORY
+                BasicItemTeleportVertex(Coordinate(0), origin, it.item, it.action, listOf())
+            }
             is SpellTeleport -> TeleportSpellVertex(Magic.valueOf(it.spell.uppercase().replace(' ', '_')), Coordinate(0), listOf())
             is Step -> CoordinateVertex(it.position, listOf())
         }
     }
-    return WebPath(mapped, path.size.toDouble())
+    return WebPath(mapped, path.cost.toDouble())
 }
@@ -0,0 +1,22 @@
+package de.torsm.osrsnav
+
+import kotlin.test.Test
+import kotlin.test.assertEquals
+
+class PathEdgeTest {
+    @Test
+    fun parsesRequirementsWithAndWithoutFields() {
+        val json = """
+            {
+                "destination": {"x": 3213, "y": 3424, "plane": 0},
+                "cost": 10,
+                "definition": {"type": "SpellTeleport", "spell": "Varrock Teleport"},
+                "requirements": ["Membership", {"Skill": {"skill": "Magic", "level": 25}}]
+            }
+        """
+        val edge = OsrsNav.gson.fromJson(json, PathEdge::class.java)
+        assertEquals(2, edge.requirements.size)
+        assertEquals("Membership", edge.requirements[0].asString)
+        assertEquals(25, edge.requirements[1].asJsonObject["Skill"].asJsonObject["level"].asInt)
+    }
+}
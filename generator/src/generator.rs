@@ -1,3 +1,4 @@
+use std::cmp::Ordering;
 use std::collections::{BTreeMap, HashSet};
 
 use expect_exit::Expected;
@@ -16,6 +17,9 @@ use model::util::RegionCache;
 #[derive(Default, Deserialize)]
 pub struct GeneratorConfig {
     excluded_location_ids: HashSet<u32>,
+    /// Positions of landmarks for the A* heuristic, spread across the map
+    #[serde(default)]
+    pub landmarks: Vec<Coordinate>,
 }
 
 pub struct NavGenerator {
@@ -37,7 +41,7 @@ impl NavGenerator {
 impl NavGenerator {
     pub fn process_map_square(&mut self, map_square: &MapSquare, loc_configs: &BTreeMap<u32, LocationConfig>) {
         if let Some(tiles) = map_square.tiles() {
-            self.process_tiles(&map_square, tiles);
+            self.process_tiles(map_square, tiles);
             if let Some(locations) = map_square.locations() {
                 self.process_locations(locations, loc_configs, tiles);
             }
@@ -203,7 +207,7 @@ impl NavGenerator {
         let c2 = c.derive(dx, dy, 0);
         let def = EdgeDefinition::Door {
             id,
-            position: c.clone(),
+            position: *c,
             action: Regex::new("^Open$").expect("Invalid regex"),
         };
         self.nav_grid.edges.insert(c.index(), Edge {
@@ -213,7 +217,7 @@ impl NavGenerator {
             requirements: vec![],
         });
         self.nav_grid.edges.insert(c2.index(), Edge {
-            destination: c.clone(),
+            destination: *c,
             cost: 2,
             definition: def,
             requirements: vec![],
@@ -229,15 +233,15 @@ impl NavGenerator {
         let mut y_flags = BLOCK_MOVEMENT_FULL;
         let mut xy_flags = BLOCK_MOVEMENT_FULL;
 
-        if dx < 0 {
-            x_flags |= BLOCK_MOVEMENT_EAST;
-        } else if dx > 0 {
-            x_flags |= BLOCK_MOVEMENT_WEST;
+        match dx.cmp(&0) {
+            Ordering::Less => x_flags |= BLOCK_MOVEMENT_EAST,
+            Ordering::Greater => x_flags |= BLOCK_MOVEMENT_WEST,
+            Ordering::Equal => {}
         }
-        if dy < 0 {
-            y_flags |= BLOCK_MOVEMENT_NORTH;
-        } else if dy > 0 {
-            y_flags |= BLOCK_MOVEMENT_SOUTH;
+        match dy.cmp(&0) {
+            Ordering::Less => y_flags |= BLOCK_MOVEMENT_NORTH,
+            Ordering::Greater => y_flags |= BLOCK_MOVEMENT_SOUTH,
+            Ordering::Equal => {}
         }
         if dx < 0 && dy < 0 {
             xy_flags |= BLOCK_MOVEMENT_NORTH_EAST;
@@ -249,15 +253,11 @@ impl NavGenerator {
             xy_flags |= BLOCK_MOVEMENT_SOUTH_WEST;
         }
         let dest_flags = self.get_flag(&dest).unwrap_or(u32::MAX);
-        if dx != 0 {
-            if dest_flags & x_flags != 0 {
-                return false;
-            }
+        if dx != 0 && dest_flags & x_flags != 0 {
+            return false;
         }
-        if dy != 0 {
-            if dest_flags & y_flags != 0 {
-                return false;
-            }
+        if dy != 0 && dest_flags & y_flags != 0 {
+            return false;
         }
         if dx != 0 && dy != 0 {
             if dest_flags & xy_flags != 0 {
@@ -274,8 +274,8 @@ impl NavGenerator {
     }
 
     pub fn transform_flags(&mut self) {
-        for index in 0..self.nav_grid.vertices.len() {
-            let c = Coordinate::from_index(index as u32);
+        for index in 0..self.nav_grid.vertices.len() as u32 {
+            let c = Coordinate::from_index(index);
             if self.get_flag(&c).unwrap_or(u32::MAX) & BLOCK_MOVEMENT_FULL > 0 {
                 continue;
             }
@@ -285,7 +285,10 @@ impl NavGenerator {
                     flags |= flag;
                 }
             }
-            self.nav_grid.vertices[index].flags = flags;
+            // Writing allocates the vertex region, keep regions without any movement empty
+            if flags != 0 {
+                self.nav_grid.vertices[index].flags = flags;
+            }
         }
     }
 
@@ -1,14 +1,13 @@
 extern crate core;
 
+use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufReader, BufWriter, Write};
+use std::io::{BufReader, BufWriter};
 use std::path::PathBuf;
 use std::sync::Arc;
 
 use clap::Parser;
 use expect_exit::{Expected, ExpectedWithError};
-use flate2::Compression;
-use flate2::write::GzEncoder;
 use rs3cache::cli::Config;
 use rs3cache::definitions::location_configs::LocationConfig;
 use rs3cache::definitions::mapsquares::MapSquares;
@@ -17,7 +16,8 @@ use serde::{Deserialize, Serialize};
 
 use generator::NavGenerator;
 use model::{Coordinate, Edge, NavGrid};
-use model::definitions::RequirementDefinition;
+use model::definitions::{QuestDefinition, RequirementDefinition};
+use model::format::{Encoding, Header};
 use model::util::RegionCache;
 
 use crate::generator::GeneratorConfig;
@@ -38,6 +38,15 @@ struct Options {
     /// YAML file with generator configuration
     #[clap(long)]
     config: Option<PathBuf>,
+    /// YAML file with quest definitions, required if custom edges have quest requirements
+    #[clap(long)]
+    quests: Option<PathBuf>,
+    /// Cache revision recorded in the NavGrid header
+    #[clap(long, default_value_t = 0)]
+    revision: u32,
+    /// Write the NavGrid uncompressed, allowing webservice to memory map it
+    #[clap(long)]
+    raw: bool,
 }
 
 fn main() {
@@ -51,7 +60,7 @@ fn main() {
         GeneratorConfig::default()
     };
 
-    let mut nav_grid = {
+    let (mut nav_grid, landmarks) = {
         println!("Processing cache...");
         let mut generator = NavGenerator::new(config);
         let cache_config = Config {
@@ -69,7 +78,7 @@ fn main() {
         });
         println!("Transforming flags...");
         generator.transform_flags();
-        generator.nav_grid
+        (generator.nav_grid, generator.config.landmarks)
     };
 
     println!("Processing custom edges...");
@@ -78,28 +87,40 @@ fn main() {
         load_custom_edges(&mut nav_grid, file).or_exit_e_("Error loading custom edges");
     }
 
+    let quests: HashMap<String, QuestDefinition> = if let Some(quests_file) = &options.quests {
+        let file = File::open(quests_file).or_exit_e_("Error opening quests file");
+        let reader = BufReader::new(file);
+        serde_yaml::from_reader(reader).or_exit_e_("Error parsing quests file")
+    } else {
+        HashMap::new()
+    };
+
     println!("Postprocessing...");
     for index in nav_grid.edges.keys() {
-        nav_grid.vertices[*index as usize].set_extra_edges(true);
+        nav_grid.vertices[*index].set_extra_edges(true);
     }
     create_groups(&mut nav_grid);
-    nav_grid.iter_edges_mut().flat_map(|e| e.requirements.iter_mut()).for_each(|r| {
-        if let RequirementDefinition::Skill { skill, .. } = r {
-            *skill = skill.to_uppercase();
+    nav_grid.iter_edges_mut().flat_map(|e| e.requirements.iter_mut()).for_each(|r| r.walk_mut(&mut |r| {
+        if let RequirementDefinition::Quest { quest, definition, .. } = r {
+            let quest_definition = quests.get(quest).or_exit(|| format!("Unknown quest {}", quest));
+            *definition = Some(quest_definition.clone());
         }
-    });
+    }));
+
+    println!("Computing {} landmarks...", landmarks.len());
+    nav_grid.landmarks = pathfinder::landmarks::compute_landmarks(&nav_grid, &landmarks);
+    for landmark in landmarks.iter().filter(|l| !nav_grid.landmarks.is_core(l.index())) {
+        println!("Landmark {} is not connected with {}, it yields no bounds", landmark, landmarks[0]);
+    }
 
     println!("Exporting nav...");
-    std::fs::create_dir_all(&options.output.parent().or_exit_("Invalid output path")).or_exit_e_("Error creating output directory");
+    std::fs::create_dir_all(options.output.parent().or_exit_("Invalid output path")).or_exit_e_("Error creating output directory");
     let nav_file = File::create(&options.output).or_exit_e_("Error creating output file");
-    let encoder = GzEncoder::new(nav_file, Compression::default());
-    let mut writer = BufWriter::new(encoder);
-    for vertex in &nav_grid.vertices {
-        let bytes = [vertex.flags, vertex.extra_edges_and_group];
-        writer.write(&bytes).or_exit_e_("Error serializing vertices");
+    let mut header = Header::new(&nav_grid, options.revision);
+    if options.raw {
+        header.encoding = Encoding::Raw;
     }
-    ciborium::ser::into_writer(&nav_grid.edges, &mut writer).or_exit_e_("Error serializing edges");
-    ciborium::ser::into_writer(&nav_grid.teleports, &mut writer).or_exit_e_("Error serializing teleports");
+    model::format::write_nav_grid(BufWriter::new(nav_file), &nav_grid, &header).or_exit_e_("Error serializing NavGrid");
 
     println!("Complete");
 }
@@ -107,16 +128,15 @@ fn main() {
 fn create_groups(nav_grid: &mut NavGrid) {
     let mut cache = RegionCache::new(false);
     let mut groups = Vec::new();
-    for index in 0..nav_grid.vertices.len() {
-        let vertex = &mut nav_grid.vertices[index];
-        if vertex.flags == 0 {
+    for index in 0..nav_grid.vertices.len() as u32 {
+        if nav_grid.vertices[index].flags == 0 {
             continue;
         }
-        vertex.set_group(1);
-        if *cache.get_mut(index as u32) {
+        nav_grid.vertices[index].set_group(1);
+        if *cache.get_mut(index) {
             continue;
         }
-        let c = Coordinate::from_index(index as u32);
+        let c = Coordinate::from_index(index);
         // Only start floods from within the surface area
         if let (1152..=3903, 2496..=4159, 0) = (c.x, c.y, c.plane) {
             let mut reachable = Vec::new();
@@ -133,11 +153,11 @@ fn create_groups(nav_grid: &mut NavGrid) {
             groups.push(reachable);
         }
     }
-    groups.sort_by(|a, b| b.len().cmp(&a.len()));
+    groups.sort_by_key(|group| std::cmp::Reverse(group.len()));
     for (index, group) in groups.iter().take(126).enumerate() {
         let group_id = index as u8 + 2;
         for index in group {
-            nav_grid.vertices[*index as usize].set_group(group_id);
+            nav_grid.vertices[*index].set_group(group_id);
         }
     }
 }
@@ -166,7 +186,7 @@ fn load_custom_edges(nav_grid: &mut NavGrid, file: File) -> serde_yaml::Result<(
         if edge.bidirectional {
             let mut edge2 = edge.edge.clone();
             let dest2 = edge2.destination;
-            edge2.destination = edge.source.clone();
+            edge2.destination = edge.source;
             nav_grid.edges.insert(dest2.index(), edge2);
         }
         nav_grid.edges.insert(edge.source.index(), edge.edge);
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Coordinate;
+
+/// Set of tiles serving as destination, e.g. a bank whose booth tiles themselves are blocked
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Area {
+    /// Tiles between both corners inclusively, on all planes from the lower to the upper corner's plane
+    Rectangle { min: Coordinate, max: Coordinate },
+    /// Tiles inside or on the outline of the polygon given by its corners as `[x, y]`, on each of the planes
+    Polygon { points: Vec<[u16; 2]>, planes: Vec<u8> },
+    /// Tiles in any of the areas, e.g. the floors of a building
+    Union { areas: Vec<Area> },
+}
+
+impl Area {
+    pub fn contains(&self, c: &Coordinate) -> bool {
+        match self {
+            Area::Rectangle { min, max } => (min.x..=max.x).contains(&c.x) && (min.y..=max.y).contains(&c.y)
+                && (min.plane..=max.plane).contains(&c.plane),
+            Area::Polygon { points, planes } => planes.contains(&c.plane) && polygon_contains(points, c.x, c.y),
+            Area::Union { areas } => areas.iter().any(|area| area.contains(c)),
+        }
+    }
+
+    pub fn validate(&self) -> bool {
+        match self {
+            Area::Rectangle { min, max } => min.validate() && max.validate() && min.x <= max.x && min.y <= max.y
+                && min.plane <= max.plane,
+            Area::Polygon { points, planes } => !points.is_empty() && !planes.is_empty() && points.iter()
+                .flat_map(|[x, y]| planes.iter().map(|plane| Coordinate { x: *x, y: *y, plane: *plane }))
+                .all(|c| c.validate()),
+            Area::Union { areas } => !areas.is_empty() && areas.iter().all(Area::validate),
+        }
+    }
+
+    /// Lowest and highest corner of the box around the area, spanning the lowest to the highest plane of any tile.
+    /// The area must be valid.
+    pub fn bounds(&self) -> (Coordinate, Coordinate) {
+        match self {
+            Area::Rectangle { min, max } => (*min, *max),
+            Area::Polygon { points, planes } => {
+                let min = Coordinate {
+                    x: points.iter().map(|p| p[0]).min().unwrap(),
+                    y: points.iter().map(|p| p[1]).min().unwrap(),
+                    plane: *planes.iter().min().unwrap(),
+                };
+                let max = Coordinate {
+                    x: points.iter().map(|p| p[0]).max().unwrap(),
+                    y: points.iter().map(|p| p[1]).max().unwrap(),
+                    plane: *planes.iter().max().unwrap(),
+                };
+                (min, max)
+            }
+            Area::Union { areas } => areas.iter().map(Area::bounds).reduce(|(min, max), (other_min, other_max)| (
+                Coordinate { x: min.x.min(other_min.x), y: min.y.min(other_min.y), plane: min.plane.min(other_min.plane) },
+                Coordinate { x: max.x.max(other_max.x), y: max.y.max(other_max.y), plane: max.plane.max(other_max.plane) },
+            )).unwrap(),
+        }
+    }
+
+    /// Visits every tile of the area, tiles of overlapping union members more than once
+    pub fn for_each_tile<F: FnMut(Coordinate)>(&self, f: &mut F) {
+        match self {
+            Area::Rectangle { min, max } => {
+                for plane in min.plane..=max.plane {
+                    for y in min.y..=max.y {
+                        for x in min.x..=max.x {
+                            f(Coordinate { x, y, plane });
+                        }
+                    }
+                }
+            }
+            Area::Polygon { points, planes } => {
+                let xs = points.iter().map(|p| p[0]);
+                let ys = points.iter().map(|p| p[1]);
+                let (min_x, max_x) = (xs.clone().min().unwrap(), xs.max().unwrap());
+                let (min_y, max_y) = (ys.clone().min().unwrap(), ys.max().unwrap());
+                for plane in planes {
+                    for y in min_y..=max_y {
+                        for x in (min_x..=max_x).filter(|x| polygon_contains(points, *x, y)) {
+                            f(Coordinate { x, y, plane: *plane });
+                        }
+                    }
+                }
+            }
+            Area::Union { areas } => areas.iter().for_each(|area| area.for_each_tile(f)),
+        }
+    }
+}
+
+/// Even-odd rule, tiles on the outline count as inside
+fn polygon_contains(points: &[[u16; 2]], x: u16, y: u16) -> bool {
+    let (x, y) = (x as i64, y as i64);
+    let mut inside = false;
+    for (i, [x1, y1]) in points.iter().enumerate() {
+        let [x2, y2] = points[(i + 1) % points.len()];
+        let (x1, y1, x2, y2) = (*x1 as i64, *y1 as i64, x2 as i64, y2 as i64);
+        let cross = (x2 - x1) * (y - y1) - (y2 - y1) * (x - x1);
+        if cross == 0 && x1.min(x2) <= x && x <= x1.max(x2) && y1.min(y2) <= y && y <= y1.max(y2) {
+            return true;
+        }
+        if (y1 > y) != (y2 > y) && ((x - x1) * (y2 - y1) < (x2 - x1) * (y - y1)) == (y2 > y1) {
+            inside = !inside;
+        }
+    }
+    inside
+}
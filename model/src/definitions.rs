@@ -1,22 +1,177 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::hash::Hash;
+use std::str::FromStr;
 
+use num_traits::One;
 pub use regex::Regex;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::{DeserializeOwned, Error};
+use serde::de::value::{Error as ValueError, StrDeserializer};
 
 use crate::Coordinate;
 
 #[derive(Debug, Default, Deserialize)]
 pub struct GameState {
+    /// Skills the server doesn't know are skipped, unlike in the generator's definitions
+    #[serde(default, deserialize_with = "known_keys")]
+    pub skills: HashMap<Skill, u8>,
     #[serde(default)]
-    pub member: bool,
+    pub inventory: HashMap<String, u32>,
     #[serde(default)]
-    pub skills: HashMap<String, u8>,
+    pub equipment: HashMap<String, u32>,
+    /// Unknown if absent, treated like an empty bank
     #[serde(default)]
-    pub items: HashMap<String, u32>,
+    pub bank: Option<HashMap<String, u32>>,
     #[serde(default)]
     pub varps: HashMap<u32, i32>,
     #[serde(default)]
     pub varbits: HashMap<u32, i32>,
+    /// Membership is taken from `Members`. Types the server doesn't know are skipped.
+    #[serde(default, deserialize_with = "known_set")]
+    pub world_types: HashSet<WorldType>,
+}
+
+/// Deserializes a set of names, leaving out those that don't deserialize, e.g. types newer than the server
+fn known_set<'de, D: Deserializer<'de>, T: DeserializeOwned + Eq + Hash>(deserializer: D) -> Result<HashSet<T>, D::Error> {
+    let names = Vec::<String>::deserialize(deserializer)?;
+    Ok(names.iter().filter_map(|name| T::deserialize(StrDeserializer::<ValueError>::new(name)).ok()).collect())
+}
+
+/// Deserializes a map by name, leaving out the entries of names that don't deserialize
+fn known_keys<'de, D: Deserializer<'de>, K: DeserializeOwned + Eq + Hash, V: Deserialize<'de>>(deserializer: D) -> Result<HashMap<K, V>, D::Error> {
+    let entries = HashMap::<String, V>::deserialize(deserializer)?;
+    Ok(entries.into_iter().filter_map(|(name, value)| Some((K::deserialize(StrDeserializer::<ValueError>::new(&name)).ok()?, value))).collect())
+}
+
+/// Types of the world the player is logged into, named like the game's world type flags
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum WorldType {
+    Members,
+    Pvp,
+    HighRisk,
+    Bounty,
+    SkillTotal,
+    LastManStanding,
+    Tournament,
+    Deadman,
+    Seasonal,
+    FreshStartWorld,
+    Beta,
+    QuestSpeedrunning,
+    PvpArena,
+}
+
+/// Serialized as the upper case name, deserialized case-insensitively
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Skill {
+    Attack,
+    Defence,
+    Strength,
+    Hitpoints,
+    Ranged,
+    Prayer,
+    Magic,
+    Cooking,
+    Woodcutting,
+    Fletching,
+    Fishing,
+    Firemaking,
+    Crafting,
+    Smithing,
+    Mining,
+    Herblore,
+    Agility,
+    Thieving,
+    Slayer,
+    Farming,
+    Runecraft,
+    Hunter,
+    Construction,
+}
+
+impl Skill {
+    pub const ALL: [Skill; 23] = [
+        Skill::Attack, Skill::Defence, Skill::Strength, Skill::Hitpoints, Skill::Ranged, Skill::Prayer, Skill::Magic,
+        Skill::Cooking, Skill::Woodcutting, Skill::Fletching, Skill::Fishing, Skill::Firemaking, Skill::Crafting,
+        Skill::Smithing, Skill::Mining, Skill::Herblore, Skill::Agility, Skill::Thieving, Skill::Slayer, Skill::Farming,
+        Skill::Runecraft, Skill::Hunter, Skill::Construction,
+    ];
+
+    pub const COMBAT: [Skill; 7] = [
+        Skill::Attack, Skill::Defence, Skill::Strength, Skill::Hitpoints, Skill::Ranged, Skill::Prayer, Skill::Magic,
+    ];
+
+    const NAMES: [&'static str; 23] = [
+        "ATTACK", "DEFENCE", "STRENGTH", "HITPOINTS", "RANGED", "PRAYER", "MAGIC", "COOKING", "WOODCUTTING", "FLETCHING",
+        "FISHING", "FIREMAKING", "CRAFTING", "SMITHING", "MINING", "HERBLORE", "AGILITY", "THIEVING", "SLAYER", "FARMING",
+        "RUNECRAFT", "HUNTER", "CONSTRUCTION",
+    ];
+
+    pub fn name(&self) -> &'static str {
+        Skill::NAMES[*self as usize]
+    }
+}
+
+impl FromStr for Skill {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Skill::ALL.into_iter()
+            .find(|skill| skill.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown skill {}", s))
+    }
+}
+
+impl Display for Skill {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Serialize for Skill {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Skill {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(|_| D::Error::unknown_variant(&name, &Skill::NAMES))
+    }
+}
+
+pub const QUEST_POINTS_VARP: u32 = 101;
+
+impl GameState {
+    pub fn is_member(&self) -> bool {
+        self.world_types.contains(&WorldType::Members)
+    }
+
+    /// Level of a skill, defaulting to the level of a new account if unknown
+    pub fn skill_level(&self, skill: Skill) -> u8 {
+        self.skills.get(&skill).copied().unwrap_or(if skill == Skill::Hitpoints { 10 } else { 1 })
+    }
+
+    /// Refer to the [wiki](https://oldschool.runescape.wiki/w/Combat_level#Mathematics)
+    pub fn combat_level(&self) -> u8 {
+        let level = |skill| self.skill_level(skill) as u32;
+        let base = level(Skill::Defence) + level(Skill::Hitpoints) + level(Skill::Prayer) / 2;
+        let melee = level(Skill::Attack) + level(Skill::Strength);
+        let ranged = level(Skill::Ranged) * 3 / 2;
+        let magic = level(Skill::Magic) * 3 / 2;
+        ((10 * base + 13 * melee.max(ranged).max(magic)) / 40) as u8
+    }
+
+    pub fn total_level(&self) -> u32 {
+        Skill::ALL.iter().map(|skill| self.skill_level(*skill) as u32).sum()
+    }
+
+    pub fn quest_points(&self) -> u32 {
+        self.varps.get(&QUEST_POINTS_VARP).copied().unwrap_or(0).max(0) as u32
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -26,7 +181,63 @@ pub enum EdgeDefinition {
     Door { id: u32, position: Coordinate, #[serde(with = "serde_regex")] action: Regex },
     GameObject { id: u32, position: Coordinate, #[serde(with = "serde_regex")] action: Regex },
     SpellTeleport { spell: String },
-    ItemTeleport { #[serde(with = "serde_regex")] item: Regex, #[serde(with = "serde_regex")] action: Regex },
+    /// Rubbed from the inventory, or operated from the equipment slot if `equipped`
+    ItemTeleport { #[serde(with = "serde_regex")] item: Regex, #[serde(with = "serde_regex")] action: Regex, #[serde(default)] equipped: bool },
+}
+
+/// Variant of an edge definition without its data
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum EdgeKind {
+    Step,
+    Door,
+    GameObject,
+    SpellTeleport,
+    ItemTeleport,
+}
+
+impl EdgeDefinition {
+    pub fn kind(&self) -> EdgeKind {
+        match self {
+            EdgeDefinition::Step { .. } => EdgeKind::Step,
+            EdgeDefinition::Door { .. } => EdgeKind::Door,
+            EdgeDefinition::GameObject { .. } => EdgeKind::GameObject,
+            EdgeDefinition::SpellTeleport { .. } => EdgeKind::SpellTeleport,
+            EdgeDefinition::ItemTeleport { .. } => EdgeKind::ItemTeleport,
+        }
+    }
+}
+
+/// Where an item requirement looks for items, `Carried` covers inventory and equipment
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ItemContainer {
+    Inventory,
+    Equipment,
+    #[default]
+    Carried,
+    Bank,
+    Any,
+}
+
+impl ItemContainer {
+    pub fn includes_bank(&self) -> bool {
+        matches!(self, ItemContainer::Bank | ItemContainer::Any)
+    }
+
+    pub fn count(&self, game_state: &GameState, item: &Regex) -> u32 {
+        let bank = game_state.bank.as_ref();
+        let containers: [Option<&HashMap<String, u32>>; 3] = match self {
+            ItemContainer::Inventory => [Some(&game_state.inventory), None, None],
+            ItemContainer::Equipment => [Some(&game_state.equipment), None, None],
+            ItemContainer::Carried => [Some(&game_state.inventory), Some(&game_state.equipment), None],
+            ItemContainer::Bank => [bank, None, None],
+            ItemContainer::Any => [Some(&game_state.inventory), Some(&game_state.equipment), bank],
+        };
+        containers.into_iter().flatten()
+            .flat_map(|container| container.iter())
+            .filter(|(i, _)| item.is_match(i))
+            .map(|(_, q)| q)
+            .sum()
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -52,29 +263,103 @@ impl Compare {
     }
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum QuestState {
+    NotStarted,
+    InProgress,
+    Completed,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuestVar {
+    Varp(u32),
+    Varbit(u32),
+}
+
+/// Where a quest's progress is stored and which values mark it as started or completed
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QuestDefinition {
+    #[serde(flatten)]
+    pub var: QuestVar,
+    #[serde(default = "i32::one")]
+    pub started: i32,
+    pub completed: i32,
+}
+
+impl QuestDefinition {
+    pub fn state(&self, game_state: &GameState) -> QuestState {
+        let value = match self.var {
+            QuestVar::Varp(index) => game_state.varps.get(&index),
+            QuestVar::Varbit(index) => game_state.varbits.get(&index),
+        }.copied().unwrap_or(0);
+        if value >= self.completed {
+            QuestState::Completed
+        } else if value >= self.started {
+            QuestState::InProgress
+        } else {
+            QuestState::NotStarted
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum RequirementDefinition {
     Membership,
-    Skill { skill: String, level: u8 },
-    Item { #[serde(with = "serde_regex")] item: Regex, quantity: u32 },
+    Skill { skill: Skill, level: u8 },
+    CombatLevel { level: u8 },
+    TotalLevel { level: u32 },
+    QuestPoints { points: u32 },
+    WorldType { world_type: WorldType },
+    Item { #[serde(with = "serde_regex")] item: Regex, quantity: u32, #[serde(default)] container: ItemContainer },
     Varp { index: u32, value: i32, compare: Compare },
     Varbit { index: u32, value: i32, compare: Compare },
+    /// The definition is resolved by name from the generator's quest table
+    Quest { quest: String, state: QuestState, #[serde(default, skip_serializing_if = "Option::is_none")] definition: Option<QuestDefinition> },
+    AnyOf(Vec<RequirementDefinition>),
+    AllOf(Vec<RequirementDefinition>),
+    Not(Box<RequirementDefinition>),
 }
 
 impl RequirementDefinition {
     pub fn is_met(&self, game_state: &GameState) -> bool {
         match self {
-            RequirementDefinition::Membership => game_state.member,
-            RequirementDefinition::Skill { skill, level } => game_state.skills.get(skill).unwrap_or(&1) >= level,
-            RequirementDefinition::Item { item, quantity } => {
-                let total: u32 = game_state.items.iter()
-                    .filter(|(i, _)| item.is_match(i))
-                    .map(|(_, q)| q)
-                    .sum();
-                total >= *quantity
-            }
+            RequirementDefinition::Membership => game_state.is_member(),
+            RequirementDefinition::Skill { skill, level } => game_state.skill_level(*skill) >= *level,
+            RequirementDefinition::CombatLevel { level } => game_state.combat_level() >= *level,
+            RequirementDefinition::TotalLevel { level } => game_state.total_level() >= *level,
+            RequirementDefinition::QuestPoints { points } => game_state.quest_points() >= *points,
+            RequirementDefinition::WorldType { world_type } => game_state.world_types.contains(world_type),
+            RequirementDefinition::Item { item, quantity, container } => container.count(game_state, item) >= *quantity,
             RequirementDefinition::Varp { index, value, compare } => game_state.varps.get(index).map(|val| compare.test(value, val)).unwrap_or(false),
             RequirementDefinition::Varbit { index, value, compare } => game_state.varbits.get(index).map(|val| compare.test(value, val)).unwrap_or(false),
+            RequirementDefinition::Quest { state, definition, .. } => definition.as_ref().map(|d| d.state(game_state) == *state).unwrap_or(false),
+            RequirementDefinition::AnyOf(requirements) => requirements.iter().any(|r| r.is_met(game_state)),
+            RequirementDefinition::AllOf(requirements) => requirements.iter().all(|r| r.is_met(game_state)),
+            RequirementDefinition::Not(requirement) => !requirement.is_met(game_state),
+        }
+    }
+
+    /// Visits this requirement and all requirements nested within it
+    pub fn walk<F: FnMut(&RequirementDefinition)>(&self, f: &mut F) {
+        f(self);
+        match self {
+            RequirementDefinition::AnyOf(requirements) | RequirementDefinition::AllOf(requirements) => {
+                requirements.iter().for_each(|r| r.walk(f));
+            }
+            RequirementDefinition::Not(requirement) => requirement.walk(f),
+            _ => {}
+        }
+    }
+
+    pub fn walk_mut<F: FnMut(&mut RequirementDefinition)>(&mut self, f: &mut F) {
+        f(self);
+        match self {
+            RequirementDefinition::AnyOf(requirements) | RequirementDefinition::AllOf(requirements) => {
+                requirements.iter_mut().for_each(|r| r.walk_mut(f));
+            }
+            RequirementDefinition::Not(requirement) => requirement.walk_mut(f),
+            _ => {}
         }
     }
 }
@@ -0,0 +1,407 @@
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crc32fast::Hasher;
+use flate2::Compression;
+use flate2::bufread::GzDecoder;
+use flate2::write::GzEncoder;
+use memmap2::Mmap;
+
+use crate::{NavGrid, Vertex};
+use crate::constants::*;
+use crate::util::{REGION_COUNT, REGION_LEN, SparseGrid};
+
+/*
+ * File layout, all integers little endian:
+ *
+ *   magic           [u8; 4]
+ *   version         u16
+ *   encoding        u8    (0 = gzip, 1 = raw)
+ *   reserved        u8
+ *   revision        u32
+ *   timestamp       u64   (seconds since unix epoch)
+ *   width           u32
+ *   height          u32
+ *   planes          u32
+ *   edge_count      u32
+ *   teleport_count  u32
+ *   payload         slots, padding, regions, cbor(edges), cbor(teleports), cbor(landmarks), crc32
+ *
+ * The payload is gzip compressed unless the encoding is raw, offsets below refer to the uncompressed file.
+ * Slots is a u32 per region pointing into the region data, slot 0 being the shared empty region. Region data
+ * starts page aligned at DATA_OFFSET and holds REGION_LEN vertices per slot as [flags, extra_edges_and_group]
+ * pairs, which matches the in-memory Vertex layout so raw files can be memory mapped and used in place.
+ * The checksum is a CRC32 of the header and the uncompressed payload preceding it, nothing may follow it.
+ */
+pub const MAGIC: [u8; 4] = *b"OSNV";
+pub const VERSION: u16 = 5;
+
+const HEADER_LEN: usize = 40;
+const SLOTS_LEN: usize = REGION_COUNT * 4;
+const DATA_OFFSET: usize = ((HEADER_LEN + SLOTS_LEN) / 4096 + 1) * 4096; // next page boundary
+const REGION_BYTES: usize = REGION_LEN * std::mem::size_of::<Vertex>();
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Encoding {
+    Gzip,
+    /// Uncompressed, can be memory mapped with [map_nav_grid]
+    Raw,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Header {
+    pub encoding: Encoding,
+    pub revision: u32,
+    pub timestamp: u64,
+    pub width: u32,
+    pub height: u32,
+    pub planes: u32,
+    pub edge_count: u32,
+    pub teleport_count: u32,
+}
+
+impl Header {
+    pub fn new(nav_grid: &NavGrid, revision: u32) -> Header {
+        let (edge_count, teleport_count) = edge_counts(nav_grid);
+        Header {
+            encoding: Encoding::Gzip,
+            revision,
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            width: WIDTH,
+            height: HEIGHT,
+            planes: PLANES,
+            edge_count,
+            teleport_count,
+        }
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&[self.encoding as u8, 0])?;
+        writer.write_all(&self.revision.to_le_bytes())?;
+        writer.write_all(&self.timestamp.to_le_bytes())?;
+        writer.write_all(&self.width.to_le_bytes())?;
+        writer.write_all(&self.height.to_le_bytes())?;
+        writer.write_all(&self.planes.to_le_bytes())?;
+        writer.write_all(&self.edge_count.to_le_bytes())?;
+        writer.write_all(&self.teleport_count.to_le_bytes())
+    }
+
+    fn bytes(&self) -> [u8; HEADER_LEN] {
+        let mut bytes = [0; HEADER_LEN];
+        self.write(&mut &mut bytes[..]).unwrap();
+        bytes
+    }
+
+    /// Reads the header along with its bytes as read, which the checksum covers
+    fn read<R: Read>(reader: &mut R) -> Result<(Header, [u8; HEADER_LEN]), FormatError> {
+        let bytes: [u8; HEADER_LEN] = read_bytes(reader)?;
+        let reader = &mut &bytes[..];
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(FormatError::InvalidMagic(magic));
+        }
+        let version = u16::from_le_bytes(read_bytes(reader)?);
+        if version != VERSION {
+            return Err(FormatError::UnsupportedVersion(version));
+        }
+        let [encoding, _] = read_bytes(reader)?;
+        let header = Header {
+            encoding: match encoding {
+                0 => Encoding::Gzip,
+                1 => Encoding::Raw,
+                _ => return Err(FormatError::UnsupportedEncoding(encoding)),
+            },
+            revision: u32::from_le_bytes(read_bytes(reader)?),
+            timestamp: u64::from_le_bytes(read_bytes(reader)?),
+            width: u32::from_le_bytes(read_bytes(reader)?),
+            height: u32::from_le_bytes(read_bytes(reader)?),
+            planes: u32::from_le_bytes(read_bytes(reader)?),
+            edge_count: u32::from_le_bytes(read_bytes(reader)?),
+            teleport_count: u32::from_le_bytes(read_bytes(reader)?),
+        };
+        if (header.width, header.height, header.planes) != (WIDTH, HEIGHT, PLANES) {
+            return Err(FormatError::DimensionMismatch(header.width, header.height, header.planes));
+        }
+        Ok((header, bytes))
+    }
+}
+
+#[derive(Debug)]
+pub enum FormatError {
+    Io(std::io::Error),
+    Encode(ciborium::ser::Error<std::io::Error>),
+    Decode(ciborium::de::Error<std::io::Error>),
+    InvalidMagic([u8; 4]),
+    UnsupportedEncoding(u8),
+    NotMappable,
+    InvalidSlot(u32),
+    UnsupportedVersion(u16),
+    DimensionMismatch(u32, u32, u32),
+    CountMismatch { edges: u32, teleports: u32 },
+    ChecksumMismatch { expected: u32, actual: u32 },
+    TrailingData,
+}
+
+impl Display for FormatError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FormatError::Io(e) => write!(f, "{}", e),
+            FormatError::Encode(e) => write!(f, "{}", e),
+            FormatError::Decode(e) => write!(f, "{}", e),
+            FormatError::InvalidMagic(magic) => write!(f, "not a NavGrid file (magic {:02X?})", magic),
+            FormatError::UnsupportedEncoding(encoding) => write!(f, "unsupported payload encoding {}", encoding),
+            FormatError::NotMappable => write!(f, "only raw encoded NavGrid files can be memory mapped"),
+            FormatError::InvalidSlot(slot) => write!(f, "vertex slot {} out of bounds", slot),
+            FormatError::UnsupportedVersion(version) => write!(f, "unsupported NavGrid format version {}, expected {}", version, VERSION),
+            FormatError::DimensionMismatch(w, h, p) => write!(f, "grid dimensions {}x{}x{} don't match {}x{}x{}", w, h, p, WIDTH, HEIGHT, PLANES),
+            FormatError::CountMismatch { edges, teleports } => write!(f, "decoded {} edges and {} teleports, header disagrees", edges, teleports),
+            FormatError::ChecksumMismatch { expected, actual } => write!(f, "checksum mismatch, expected {:08X}, got {:08X}", expected, actual),
+            FormatError::TrailingData => write!(f, "unexpected data after the checksum"),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+impl From<std::io::Error> for FormatError {
+    fn from(e: std::io::Error) -> Self {
+        FormatError::Io(e)
+    }
+}
+
+impl From<ciborium::ser::Error<std::io::Error>> for FormatError {
+    fn from(e: ciborium::ser::Error<std::io::Error>) -> Self {
+        FormatError::Encode(e)
+    }
+}
+
+impl From<ciborium::de::Error<std::io::Error>> for FormatError {
+    fn from(e: ciborium::de::Error<std::io::Error>) -> Self {
+        FormatError::Decode(e)
+    }
+}
+
+pub fn write_nav_grid<W: Write>(mut writer: W, nav_grid: &NavGrid, header: &Header) -> Result<(), FormatError> {
+    let header_bytes = header.bytes();
+    writer.write_all(&header_bytes)?;
+    match header.encoding {
+        Encoding::Gzip => {
+            let mut encoder = GzEncoder::new(writer, Compression::default());
+            write_payload(&mut encoder, nav_grid, &header_bytes)?;
+            encoder.finish()?.flush()?;
+        }
+        Encoding::Raw => {
+            write_payload(&mut writer, nav_grid, &header_bytes)?;
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+fn write_payload<W: Write>(writer: W, nav_grid: &NavGrid, header: &[u8]) -> Result<(), FormatError> {
+    let mut writer = Checksummed::new(BufWriter::new(writer), header);
+    for slot in nav_grid.vertices.slots() {
+        writer.write_all(&slot.to_le_bytes())?;
+    }
+    writer.write_all(&[0; DATA_OFFSET - HEADER_LEN - SLOTS_LEN])?;
+    let mut buf = vec![0; REGION_BYTES];
+    for region in nav_grid.vertices.data().chunks_exact(REGION_LEN) {
+        for (vertex, bytes) in region.iter().zip(buf.chunks_exact_mut(2)) {
+            bytes.copy_from_slice(&[vertex.flags, vertex.extra_edges_and_group]);
+        }
+        writer.write_all(&buf)?;
+    }
+    ciborium::ser::into_writer(&nav_grid.edges, &mut writer)?;
+    ciborium::ser::into_writer(&nav_grid.teleports, &mut writer)?;
+    ciborium::ser::into_writer(&nav_grid.landmarks, &mut writer)?;
+    let checksum = writer.hasher.finalize();
+    let mut writer = writer.inner;
+    writer.write_all(&checksum.to_le_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn read_header<R: Read>(mut reader: R) -> Result<Header, FormatError> {
+    Header::read(&mut reader).map(|(header, _)| header)
+}
+
+/// Reads a NavGrid of either encoding into owned memory
+pub fn read_nav_grid<R: Read>(reader: R) -> Result<(Header, NavGrid), FormatError> {
+    let mut reader = BufReader::new(reader);
+    let (header, header_bytes) = Header::read(&mut reader)?;
+    let nav_grid = match header.encoding {
+        Encoding::Gzip => {
+            // Reading from the buffered reader, the decoder only consumes the compressed stream
+            let mut decoder = GzDecoder::new(reader);
+            let nav_grid = read_payload(&mut decoder, &header_bytes)?;
+            check_end(decoder.get_mut())?;
+            nav_grid
+        }
+        Encoding::Raw => read_payload(reader, &header_bytes)?,
+    };
+    check_counts(&header, &nav_grid)?;
+    Ok((header, nav_grid))
+}
+
+fn read_payload<R: Read>(reader: R, header: &[u8]) -> Result<NavGrid, FormatError> {
+    let mut reader = Checksummed::new(BufReader::new(reader), header);
+    let mut slots = vec![0; REGION_COUNT];
+    for slot in &mut slots {
+        *slot = u32::from_le_bytes(read_bytes(&mut reader)?);
+    }
+    let slot_count = count_slots(&slots)?;
+    reader.read_exact(&mut [0; DATA_OFFSET - HEADER_LEN - SLOTS_LEN])?;
+    let mut data = Vec::with_capacity(slot_count * REGION_LEN);
+    let mut buf = vec![0; REGION_BYTES];
+    for _ in 0..slot_count {
+        reader.read_exact(&mut buf)?;
+        data.extend(buf.chunks_exact(2).map(|bytes| Vertex { flags: bytes[0], extra_edges_and_group: bytes[1] }));
+    }
+    let mut nav_grid = NavGrid::new();
+    nav_grid.vertices = SparseGrid::from_parts(slots, data);
+    nav_grid.edges = ciborium::de::from_reader(&mut reader)?;
+    nav_grid.teleports = ciborium::de::from_reader(&mut reader)?;
+    nav_grid.landmarks = ciborium::de::from_reader(&mut reader)?;
+    let actual = reader.hasher.finalize();
+    let expected = u32::from_le_bytes(read_bytes(&mut reader.inner)?);
+    if expected != actual {
+        return Err(FormatError::ChecksumMismatch { expected, actual });
+    }
+    check_end(&mut reader.inner)?;
+    Ok(nav_grid)
+}
+
+fn check_end<R: BufRead>(reader: &mut R) -> Result<(), FormatError> {
+    if !reader.fill_buf()?.is_empty() {
+        return Err(FormatError::TrailingData);
+    }
+    Ok(())
+}
+
+/// Memory maps a raw encoded NavGrid file and uses its vertices in place, edges, teleports and landmarks are
+/// decoded. The file must not be modified while the NavGrid is alive.
+pub fn map_nav_grid(file: &File) -> Result<(Header, NavGrid), FormatError> {
+    let map = Arc::new(unsafe { Mmap::map(file)? });
+    let (header, _) = Header::read(&mut &map[..])?;
+    if header.encoding != Encoding::Raw {
+        return Err(FormatError::NotMappable);
+    }
+    if map.len() < DATA_OFFSET + REGION_BYTES + 4 {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+    let (checksummed, checksum) = map.split_at(map.len() - 4);
+    let expected = u32::from_le_bytes(checksum.try_into().unwrap());
+    let actual = crc32fast::hash(checksummed);
+    if expected != actual {
+        return Err(FormatError::ChecksumMismatch { expected, actual });
+    }
+    let slots: Vec<u32> = map[HEADER_LEN..HEADER_LEN + SLOTS_LEN].chunks_exact(4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        .collect();
+    let slot_count = count_slots(&slots)?;
+    let data_end = DATA_OFFSET + slot_count * REGION_BYTES;
+    if map.len() < data_end + 4 {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+    let mut nav_grid = NavGrid::new();
+    nav_grid.vertices = if cfg!(target_endian = "little") {
+        // Slots are all below slot_count by count_slots and the map holds slot_count regions, the slot table
+        // follows the 40 byte header and the data starts on a page boundary, so both are aligned as the map itself
+        // is page aligned. Vertex is repr(C) of two bytes, valid for any bit pattern.
+        unsafe { SparseGrid::from_mapped(map.clone(), HEADER_LEN, DATA_OFFSET, slot_count) }
+    } else {
+        let data = map[DATA_OFFSET..data_end].chunks_exact(2)
+            .map(|bytes| Vertex { flags: bytes[0], extra_edges_and_group: bytes[1] })
+            .collect();
+        SparseGrid::from_parts(slots, data)
+    };
+    let mut reader = &map[data_end..map.len() - 4];
+    nav_grid.edges = ciborium::de::from_reader(&mut reader)?;
+    nav_grid.teleports = ciborium::de::from_reader(&mut reader)?;
+    nav_grid.landmarks = ciborium::de::from_reader(&mut reader)?;
+    if !reader.is_empty() {
+        return Err(FormatError::TrailingData);
+    }
+    check_counts(&header, &nav_grid)?;
+    Ok((header, nav_grid))
+}
+
+/// Opens a NavGrid file, memory mapping it if it is raw encoded
+pub fn open_nav_grid(path: impl AsRef<Path>) -> Result<(Header, NavGrid), FormatError> {
+    let mut file = File::open(path)?;
+    let header = read_header(&mut file)?;
+    if header.encoding == Encoding::Raw {
+        map_nav_grid(&file)
+    } else {
+        file.rewind()?;
+        read_nav_grid(file)
+    }
+}
+
+fn count_slots(slots: &[u32]) -> Result<usize, FormatError> {
+    let slot_count = slots.iter().filter(|slot| **slot != 0).count() + 1;
+    match slots.iter().find(|slot| **slot as usize >= slot_count) {
+        Some(slot) => Err(FormatError::InvalidSlot(*slot)),
+        None => Ok(slot_count),
+    }
+}
+
+fn check_counts(header: &Header, nav_grid: &NavGrid) -> Result<(), FormatError> {
+    let (edges, teleports) = edge_counts(nav_grid);
+    if (edges, teleports) != (header.edge_count, header.teleport_count) {
+        return Err(FormatError::CountMismatch { edges, teleports });
+    }
+    Ok(())
+}
+
+fn edge_counts(nav_grid: &NavGrid) -> (u32, u32) {
+    let edges = nav_grid.edges.iter_all().map(|(_, v)| v.len() as u32).sum();
+    (edges, nav_grid.teleports.len() as u32)
+}
+
+fn read_bytes<R: Read, const N: usize>(reader: &mut R) -> std::io::Result<[u8; N]> {
+    let mut buf = [0; N];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+struct Checksummed<T> {
+    inner: T,
+    hasher: Hasher,
+}
+
+impl<T> Checksummed<T> {
+    /// Checksums the header, then whatever passes through
+    fn new(inner: T, header: &[u8]) -> Checksummed<T> {
+        let mut hasher = Hasher::new();
+        hasher.update(header);
+        Checksummed { inner, hasher }
+    }
+}
+
+impl<T: Read> Read for Checksummed<T> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+impl<T: Write> Write for Checksummed<T> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
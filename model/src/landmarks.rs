@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Coordinate;
+use crate::constants::*;
+use crate::util::{region_index, region_offset, REGION_COUNT};
+
+/// Chunks per region, the granularity of landmark bounds
+pub const REGION_CHUNKS: usize = ((REGION_SIZE / 8) * (REGION_SIZE / 8)) as usize;
+
+/// Chunk within the region of a vertex at the region offset
+pub fn region_chunk(offset: usize) -> usize {
+    let size = REGION_SIZE as usize;
+    (offset / size / 8) * (size / 8) + offset % size / 8
+}
+
+/// Landmarks with travel cost bounds to and from every chunk, used for the A* heuristic (ALT).
+///
+/// Costs are computed over vertex steps and edges regardless of their requirements, so they are lower bounds for any
+/// game state. Teleports are excluded, as a search only takes them from its start. Bounds are per chunk instead of
+/// per vertex to keep the NavGrid small and only cover the core, the vertices that can reach and be reached from the
+/// landmarks. Vertices outside of it, like enclosed pockets, would otherwise leave most chunks without bounds.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Landmarks {
+    /// Bitset of core vertices per region, empty for regions without any
+    core: Vec<Vec<u64>>,
+    pub landmarks: Vec<Landmark>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Landmark {
+    pub position: Coordinate,
+    /// Bounds of the chunks per region, empty for regions without core vertices
+    pub bounds: Vec<Vec<Bounds>>,
+}
+
+/// Costs between a landmark and the core vertices of a chunk, `u32::MAX` if there are none or they are unreachable
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Bounds {
+    pub from_min: u32,
+    pub from_max: u32,
+    pub to_min: u32,
+    pub to_max: u32,
+}
+
+impl Default for Landmarks {
+    fn default() -> Landmarks {
+        Landmarks::new(vec![Vec::new(); REGION_COUNT], Vec::new())
+    }
+}
+
+impl Landmarks {
+    pub fn new(core: Vec<Vec<u64>>, landmarks: Vec<Landmark>) -> Landmarks {
+        Landmarks { core, landmarks }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.landmarks.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.landmarks.len()
+    }
+
+    pub fn is_core(&self, index: u32) -> bool {
+        let bits = &self.core[region_index(index) as usize];
+        let offset = region_offset(index);
+        !bits.is_empty() && bits[offset / 64] & (1 << (offset % 64)) != 0
+    }
+
+    /// Admissible estimate of the remaining cost from a vertex to the target, the best bound of all landmarks.
+    /// Zero unless both are core vertices.
+    pub fn estimate(&self, index: u32, target_index: u32) -> u32 {
+        if !self.is_core(index) || !self.is_core(target_index) {
+            return 0;
+        }
+        self.landmarks.iter().map(|l| l.lower_bound(index, target_index)).max().unwrap_or(0)
+    }
+}
+
+impl Landmark {
+    pub fn chunk_bounds(&self, index: u32) -> Option<&Bounds> {
+        self.bounds[region_index(index) as usize].get(region_chunk(region_offset(index)))
+    }
+
+    /// Lower bound of the cost between two core vertices by triangle inequality. Core vertices reach every landmark
+    /// or none, in the latter case all bounds are `u32::MAX`.
+    pub fn lower_bound(&self, index: u32, target_index: u32) -> u32 {
+        match (self.chunk_bounds(index), self.chunk_bounds(target_index)) {
+            (Some(v), Some(t)) => {
+                let forward = t.from_min.saturating_sub(v.from_max);
+                let backward = v.to_min.saturating_sub(t.to_max);
+                forward.max(backward)
+            }
+            _ => 0,
+        }
+    }
+}
@@ -2,27 +2,47 @@ use std::fmt::{Display, Formatter};
 
 pub use multimap::MultiMap;
 use num_traits::One;
+use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 
 use crate::constants::*;
 use crate::definitions::{EdgeDefinition, RequirementDefinition};
+use crate::landmarks::Landmarks;
+use crate::util::SparseGrid;
 
+pub mod area;
 pub mod definitions;
+pub mod format;
+pub mod landmarks;
 pub mod constants;
 pub mod util;
 
 pub struct NavGrid {
-    pub vertices: Vec<Vertex>,
+    pub vertices: SparseGrid<Vertex>,
     pub edges: MultiMap<u32, Edge>,
     pub teleports: Vec<Edge>,
+    pub landmarks: Landmarks,
+    /// Source vertex and position in its edges by destination, built on first use
+    reverse_edges: OnceCell<MultiMap<u32, (u32, usize)>>,
+    /// Highest edge cost, bounds the cost range search queues have to cover
+    max_cost: OnceCell<u32>,
+}
+
+impl Default for NavGrid {
+    fn default() -> NavGrid {
+        NavGrid::new()
+    }
 }
 
 impl NavGrid {
     pub fn new() -> NavGrid {
         NavGrid {
-            vertices: vec![Vertex::default(); (WIDTH * HEIGHT * PLANES) as usize],
+            vertices: SparseGrid::new(),
             edges: MultiMap::new(),
             teleports: Vec::new(),
+            landmarks: Landmarks::default(),
+            reverse_edges: OnceCell::new(),
+            max_cost: OnceCell::new(),
         }
     }
 
@@ -33,9 +53,31 @@ impl NavGrid {
     pub fn iter_edges_mut(&mut self) -> impl Iterator<Item=&mut Edge> {
         self.edges.iter_all_mut().flat_map(|(_, v)| v).chain(self.teleports.iter_mut())
     }
+
+    /// Highest cost of any edge or step, computed on first use, edges must not change afterwards.
+    pub fn max_cost(&self) -> u32 {
+        *self.max_cost.get_or_init(|| self.iter_edges().map(|edge| edge.cost).max().unwrap_or(1).max(1))
+    }
+
+    /// Edges leading to the vertex with their source vertex and position among the source's edges, teleports
+    /// excluded. The reverse adjacency is built on first use, edges must not be added or removed afterwards.
+    pub fn edges_to(&self, index: u32) -> impl Iterator<Item=(u32, usize, &Edge)> {
+        let reverse_edges = self.reverse_edges.get_or_init(|| {
+            let mut reverse_edges = MultiMap::new();
+            for (source, edges) in self.edges.iter_all() {
+                for (i, edge) in edges.iter().enumerate() {
+                    reverse_edges.insert(edge.destination.index(), (*source, i));
+                }
+            }
+            reverse_edges
+        });
+        reverse_edges.get_vec(&index).into_iter().flatten()
+            .map(|(source, i)| (*source, *i, &self.edges.get_vec(source).unwrap()[*i]))
+    }
 }
 
 #[derive(Default, Eq, PartialEq, Clone, Copy, Debug)]
+#[repr(C)] // memory mapped from raw NavGrid files, see format
 pub struct Vertex {
     pub flags: u8,
     pub extra_edges_and_group: u8, // surely rust will soon support bit fields
@@ -126,6 +168,84 @@ impl Coordinate {
         self.y = (self.y as i16 + dy) as u16;
         self.plane = (self.plane as i8 + dplane) as u8;
     }
+
+    /// Like derive, but None if the result is out of bounds instead of wrapping
+    pub fn checked_derive(&self, dx: i16, dy: i16, dplane: i8) -> Option<Coordinate> {
+        let c = Coordinate {
+            x: self.x.checked_add_signed(dx)?,
+            y: self.y.checked_add_signed(dy)?,
+            plane: self.plane.checked_add_signed(dplane)?,
+        };
+        c.validate().then_some(c)
+    }
+
+    /// Distances ignore the plane, compare planes separately where it matters
+    pub fn chebyshev_distance(&self, other: &Coordinate) -> u32 {
+        self.x.abs_diff(other.x).max(self.y.abs_diff(other.y)) as u32
+    }
+
+    pub fn manhattan_distance(&self, other: &Coordinate) -> u32 {
+        self.x.abs_diff(other.x) as u32 + self.y.abs_diff(other.y) as u32
+    }
+
+    pub fn euclidean_distance(&self, other: &Coordinate) -> f64 {
+        (self.x.abs_diff(other.x) as f64).hypot(self.y.abs_diff(other.y) as f64)
+    }
+
+    pub fn region_x(&self) -> u16 {
+        self.x >> 6
+    }
+
+    pub fn region_y(&self) -> u16 {
+        self.y >> 6
+    }
+
+    /// The game's region id, `region_x << 8 | region_y`
+    pub fn region_id(&self) -> u32 {
+        (self.region_x() as u32) << 8 | self.region_y() as u32
+    }
+
+    pub fn from_region_id(region_id: u32, local_x: u8, local_y: u8, plane: u8) -> Coordinate {
+        Coordinate::from_map_square((region_id >> 8) as u8, region_id as u8, local_x, local_y, plane)
+    }
+
+    /// Chunks are the 8x8 tile zones regions are built from
+    pub fn chunk_x(&self) -> u16 {
+        self.x >> 3
+    }
+
+    pub fn chunk_y(&self) -> u16 {
+        self.y >> 3
+    }
+
+    /// South-west tile of the chunk
+    pub fn chunk_base(&self) -> Coordinate {
+        Coordinate { x: self.x & !7, y: self.y & !7, plane: self.plane }
+    }
+
+    /// RuneLite's shortest path plugin format, `plane << 30 | y << 15 | x`.
+    /// RuneLite's WorldPoint coord and RuneMate's coordinate hash use the id format instead.
+    pub fn runelite_packed(&self) -> i32 {
+        ((self.plane as u32 & 0x3) << 30 | (self.y as u32 & 0x7FFF) << 15 | (self.x as u32 & 0x7FFF)) as i32
+    }
+
+    pub fn from_runelite_packed(packed: i32) -> Coordinate {
+        let packed = packed as u32;
+        Coordinate {
+            x: (packed & 0x7FFF) as u16,
+            y: ((packed >> 15) & 0x7FFF) as u16,
+            plane: (packed >> 30) as u8,
+        }
+    }
+
+    /// RuneMate's coordinate hash, identical to id
+    pub fn runemate_hash(&self) -> i32 {
+        self.id() as i32
+    }
+
+    pub fn from_runemate_hash(hash: i32) -> Coordinate {
+        Coordinate::from_id(hash as u32)
+    }
 }
 
 impl Display for Coordinate {
@@ -1,17 +1,53 @@
+use std::ops::{Deref, Index, IndexMut};
+use std::sync::Arc;
+
+use memmap2::Mmap;
+
 use crate::constants::*;
 
 type Region<T> = [T; (REGION_SIZE * REGION_SIZE) as usize];
 
+pub const REGION_LEN: usize = (REGION_SIZE * REGION_SIZE) as usize;
+pub const REGION_COUNT: usize = (HORIZONTAL_REGIONS * VERTICAL_REGIONS * PLANES) as usize;
+/// Released regions a RegionCache keeps for reuse, more than most searches allocate. Memory of a single
+/// exceptionally large search is freed instead of held on to by the thread for good.
+pub const MAX_SPARE_REGIONS: usize = 256;
+
+pub fn region_index(index: u32) -> u32 {
+    let x = index % WIDTH;
+    let y = index / WIDTH;
+    (y / REGION_SIZE) * HORIZONTAL_REGIONS + x / REGION_SIZE
+}
+
+pub fn region_offset(index: u32) -> usize {
+    let x = index % WIDTH;
+    let y = index / WIDTH;
+    ((y % REGION_SIZE) * REGION_SIZE + x % REGION_SIZE) as usize
+}
+
+/// Inverse of region_index and region_offset
+pub fn vertex_index(region_index: u32, offset: usize) -> u32 {
+    let x = (region_index % HORIZONTAL_REGIONS) * REGION_SIZE + offset as u32 % REGION_SIZE;
+    let y = (region_index / HORIZONTAL_REGIONS) * REGION_SIZE + offset as u32 / REGION_SIZE;
+    y * WIDTH + x
+}
+
 pub struct RegionCache<T> {
     default_value: T,
     regions: Vec<Option<Box<Region<T>>>>,
+    /// Indices of the regions in use
+    allocated: Vec<u32>,
+    /// Regions released by reset, reused before boxing new ones
+    spare: Vec<Box<Region<T>>>,
 }
 
 impl<T: Clone + Default> Default for RegionCache<T> {
     fn default() -> RegionCache<T> {
         RegionCache {
             default_value: Default::default(),
-            regions: vec![None; (HORIZONTAL_REGIONS * VERTICAL_REGIONS * PLANES) as usize],
+            regions: vec![None; REGION_COUNT],
+            allocated: Vec::new(),
+            spare: Vec::new(),
         }
     }
 }
@@ -20,8 +56,19 @@ impl<T: Copy> RegionCache<T> {
     pub fn new(default_value: T) -> RegionCache<T> {
         RegionCache {
             default_value,
-            regions: vec![None; (HORIZONTAL_REGIONS * VERTICAL_REGIONS * PLANES) as usize],
+            regions: vec![None; REGION_COUNT],
+            allocated: Vec::new(),
+            spare: Vec::new(),
+        }
+    }
+
+    /// Releases all regions, keeping the memory of up to MAX_SPARE_REGIONS of them for the next use. Costs are
+    /// proportional to the regions in use, not to the map size.
+    pub fn reset(&mut self) {
+        for region_index in self.allocated.drain(..) {
+            self.spare.extend(self.regions[region_index as usize].take());
         }
+        self.spare.truncate(MAX_SPARE_REGIONS);
     }
 
     /*
@@ -29,20 +76,25 @@ impl<T: Copy> RegionCache<T> {
      * can't allocate regions. RefCell possible solution, but overhead is nonsensical.
     */
     pub fn get_mut(&mut self, index: u32) -> &mut T {
-        let x = index % WIDTH;
-        let y = index / WIDTH;
-        let region_index = (y / REGION_SIZE) * HORIZONTAL_REGIONS + x / REGION_SIZE;
-        let region = self.regions[region_index as usize]
-            .get_or_insert_with(|| Box::new([self.default_value; (REGION_SIZE * REGION_SIZE) as usize]));
-        &mut region[((y % REGION_SIZE) * REGION_SIZE + x % REGION_SIZE) as usize]
+        let region_index = region_index(index);
+        let slot = &mut self.regions[region_index as usize];
+        if slot.is_none() {
+            let region = match self.spare.pop() {
+                Some(mut region) => {
+                    region.fill(self.default_value);
+                    region
+                }
+                None => Box::new([self.default_value; REGION_LEN]),
+            };
+            self.allocated.push(region_index);
+            *slot = Some(region);
+        }
+        &mut slot.as_mut().unwrap()[region_offset(index)]
     }
 
     pub fn get(&self, index: u32) -> Option<&T> {
-        let x = index % WIDTH;
-        let y = index / WIDTH;
-        let region_index = (y / REGION_SIZE) * HORIZONTAL_REGIONS + x / REGION_SIZE;
-        let region = self.regions[region_index as usize].as_ref()?;
-        Some(&region[((y % REGION_SIZE) * REGION_SIZE + x % REGION_SIZE) as usize])
+        let region = self.regions[region_index(index) as usize].as_ref()?;
+        Some(&region[region_offset(index)])
     }
 
     pub fn mem_usage(&self) -> usize {
@@ -51,3 +103,261 @@ impl<T: Copy> RegionCache<T> {
         }).sum()
     }
 }
+
+/// Region-granular sparse storage covering the whole map. Regions that were never written to all share slot 0,
+/// a single region filled with the default value, so they cost one table entry each.
+pub struct SparseGrid<T: 'static> {
+    slots: Backing<u32>,
+    data: Backing<T>,
+}
+
+impl<T: Copy + Default> Default for SparseGrid<T> {
+    fn default() -> SparseGrid<T> {
+        SparseGrid {
+            slots: Backing::Owned(vec![0; REGION_COUNT]),
+            data: Backing::Owned(vec![T::default(); REGION_LEN]),
+        }
+    }
+}
+
+impl<T: Copy + Default> SparseGrid<T> {
+    pub fn new() -> SparseGrid<T> {
+        Default::default()
+    }
+
+    /// Uses the slot table and region data in place from a memory mapped file. The first write copies
+    /// them into owned memory.
+    ///
+    /// # Safety
+    /// The caller guarantees that
+    /// - `T` is valid for any bit pattern and laid out as stored in the file, e.g. `#[repr(C)]` of bytes
+    /// - `slots_offset` is aligned for `u32` and `data_offset` for `T`, checked by an assertion as well
+    /// - `map` holds `REGION_COUNT` slots at `slots_offset` and `slot_count` regions at `data_offset`
+    /// - every slot is smaller than `slot_count`, otherwise indexing panics instead of reading out of bounds
+    /// - the mapped file isn't modified while the grid is alive
+    pub(crate) unsafe fn from_mapped(map: Arc<Mmap>, slots_offset: usize, data_offset: usize, slot_count: usize) -> SparseGrid<T> {
+        SparseGrid {
+            slots: Backing::mapped(map.clone(), slots_offset, REGION_COUNT),
+            data: Backing::mapped(map, data_offset, slot_count * REGION_LEN),
+        }
+    }
+
+    pub(crate) fn from_parts(slots: Vec<u32>, data: Vec<T>) -> SparseGrid<T> {
+        SparseGrid {
+            slots: Backing::Owned(slots),
+            data: Backing::Owned(data),
+        }
+    }
+
+    /// Slot of every region, slot 0 being the shared empty region
+    pub(crate) fn slots(&self) -> &[u32] {
+        &self.slots
+    }
+
+    /// Values of all slots, REGION_LEN per slot
+    pub(crate) fn data(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.data, Backing::Mapped { .. })
+    }
+
+    /// Number of addressable values, allocated or not
+    pub fn len(&self) -> usize {
+        REGION_COUNT * REGION_LEN
+    }
+
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    pub fn get(&self, index: u32) -> &T {
+        let slot = self.slots[region_index(index) as usize] as usize;
+        &self.data[slot * REGION_LEN + region_offset(index)]
+    }
+
+    pub fn get_mut(&mut self, index: u32) -> &mut T {
+        let slot = self.allocate(region_index(index)) as usize;
+        &mut self.data.to_mut()[slot * REGION_LEN + region_offset(index)]
+    }
+
+    pub fn region(&self, region_index: u32) -> Option<&[T]> {
+        match self.slots[region_index as usize] as usize {
+            0 => None,
+            slot => Some(&self.data[slot * REGION_LEN..(slot + 1) * REGION_LEN]),
+        }
+    }
+
+    pub fn region_mut(&mut self, region_index: u32) -> &mut [T] {
+        let slot = self.allocate(region_index) as usize;
+        &mut self.data.to_mut()[slot * REGION_LEN..(slot + 1) * REGION_LEN]
+    }
+
+    /// Iterates allocated regions in ascending region index order
+    pub fn regions(&self) -> impl Iterator<Item=(u32, &[T])> {
+        (0..REGION_COUNT as u32).filter_map(|i| Some((i, self.region(i)?)))
+    }
+
+    pub fn region_count(&self) -> usize {
+        self.data.len() / REGION_LEN - 1
+    }
+
+    /// Heap memory owned by the grid, mapped memory is not counted
+    pub fn mem_usage(&self) -> usize {
+        self.slots.mem_usage() + self.data.mem_usage()
+    }
+
+    fn allocate(&mut self, region_index: u32) -> u32 {
+        let slot = self.slots[region_index as usize];
+        if slot != 0 {
+            return slot;
+        }
+        let data = self.data.to_mut();
+        let slot = (data.len() / REGION_LEN) as u32;
+        data.resize(data.len() + REGION_LEN, T::default());
+        self.slots.to_mut()[region_index as usize] = slot;
+        slot
+    }
+}
+
+impl<T: Copy + Default> Index<u32> for SparseGrid<T> {
+    type Output = T;
+
+    fn index(&self, index: u32) -> &T {
+        self.get(index)
+    }
+}
+
+/// Allocates the region on write, only index mutably where a value is actually stored
+impl<T: Copy + Default> IndexMut<u32> for SparseGrid<T> {
+    fn index_mut(&mut self, index: u32) -> &mut T {
+        self.get_mut(index)
+    }
+}
+
+enum Backing<T: 'static> {
+    Owned(Vec<T>),
+    /// Slice into the memory map, which is kept alive for as long as the slice
+    Mapped { slice: &'static [T], _map: Arc<Mmap> },
+}
+
+impl<T: Copy> Backing<T> {
+    /// # Safety
+    /// `map` must hold `len` values of `T` at `offset`, valid for any bit pattern. Alignment is asserted.
+    unsafe fn mapped(map: Arc<Mmap>, offset: usize, len: usize) -> Backing<T> {
+        let bytes = &map[offset..offset + len * std::mem::size_of::<T>()];
+        assert_eq!(bytes.as_ptr().align_offset(std::mem::align_of::<T>()), 0, "Misaligned mapping");
+        let slice = std::slice::from_raw_parts(bytes.as_ptr() as *const T, len);
+        Backing::Mapped { slice, _map: map }
+    }
+
+    fn to_mut(&mut self) -> &mut Vec<T> {
+        if let Backing::Mapped { slice, .. } = self {
+            *self = Backing::Owned(slice.to_vec());
+        }
+        match self {
+            Backing::Owned(vec) => vec,
+            Backing::Mapped { .. } => unreachable!(),
+        }
+    }
+
+    fn mem_usage(&self) -> usize {
+        match self {
+            Backing::Owned(vec) => std::mem::size_of_val(vec.as_slice()),
+            Backing::Mapped { .. } => 0,
+        }
+    }
+}
+
+impl<T> Deref for Backing<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self {
+            Backing::Owned(vec) => vec,
+            Backing::Mapped { slice, .. } => slice,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(x: u32, y: u32, plane: u32) -> u32 {
+        plane * WIDTH * HEIGHT + y * WIDTH + x
+    }
+
+    #[test]
+    fn unwritten_regions_share_the_empty_slot() {
+        let grid: SparseGrid<u16> = SparseGrid::new();
+        assert!(grid.slots().iter().all(|slot| *slot == 0));
+        assert_eq!(grid.data().len(), REGION_LEN);
+        assert_eq!(grid.region_count(), 0);
+        assert_eq!(*grid.get(index(0, 0, 0)), 0);
+        assert_eq!(*grid.get(index(WIDTH - 1, HEIGHT - 1, PLANES - 1)), 0);
+        assert!(grid.regions().next().is_none());
+    }
+
+    #[test]
+    fn writing_allocates_only_its_region() {
+        let mut grid: SparseGrid<u16> = SparseGrid::new();
+        *grid.get_mut(index(100, 200, 1)) = 7;
+        assert_eq!(grid.region_count(), 1);
+        let region = region_index(index(100, 200, 1));
+        assert_eq!(grid.slots()[region as usize], 1);
+        assert_eq!(grid.slots().iter().filter(|slot| **slot != 0).count(), 1);
+        assert_eq!(*grid.get(index(100, 200, 1)), 7);
+        assert_eq!(*grid.get(index(101, 200, 1)), 0);
+        // The shared empty region stays empty
+        assert!(grid.data()[..REGION_LEN].iter().all(|value| *value == 0));
+        assert_eq!(*grid.get(index(100, 200, 0)), 0);
+        // Writing again reuses the region
+        *grid.get_mut(index(101, 200, 1)) = 8;
+        assert_eq!(grid.region_count(), 1);
+        assert_eq!(grid.regions().map(|(i, _)| i).collect::<Vec<_>>(), vec![region]);
+    }
+
+    #[test]
+    fn spare_regions_are_bounded() {
+        let mut cache = RegionCache::new(u32::MAX);
+        for round in 0..3 {
+            for region in 0..MAX_SPARE_REGIONS as u32 + 10 {
+                *cache.get_mut(vertex_index(region, 0)) = round;
+            }
+            cache.reset();
+            assert_eq!(cache.spare.len(), MAX_SPARE_REGIONS);
+            assert!(cache.allocated.is_empty());
+            assert!(cache.regions.iter().all(Option::is_none));
+        }
+        // Reused regions are cleared
+        assert_eq!(*cache.get_mut(vertex_index(0, 0)), u32::MAX);
+        assert_eq!(cache.spare.len(), MAX_SPARE_REGIONS - 1);
+    }
+
+    #[test]
+    fn indices_map_across_region_edges() {
+        let last = REGION_SIZE - 1;
+        assert_eq!(region_index(index(last, last, 0)), 0);
+        assert_eq!(region_offset(index(last, last, 0)), REGION_LEN - 1);
+        assert_eq!(region_index(index(REGION_SIZE, 0, 0)), 1);
+        assert_eq!(region_offset(index(REGION_SIZE, 0, 0)), 0);
+        assert_eq!(region_index(index(0, REGION_SIZE, 0)), HORIZONTAL_REGIONS);
+        assert_eq!(region_index(index(WIDTH - 1, HEIGHT - 1, 0)), HORIZONTAL_REGIONS * VERTICAL_REGIONS - 1);
+        assert_eq!(region_index(index(0, 0, 1)), HORIZONTAL_REGIONS * VERTICAL_REGIONS);
+        assert_eq!(region_index(index(WIDTH - 1, HEIGHT - 1, PLANES - 1)) as usize, REGION_COUNT - 1);
+        for index in [index(last, last, 0), index(REGION_SIZE, last, 0), index(last, REGION_SIZE, 2), index(WIDTH - 1, HEIGHT - 1, PLANES - 1)] {
+            assert_eq!(vertex_index(region_index(index), region_offset(index)), index);
+        }
+
+        let mut grid: SparseGrid<u16> = SparseGrid::new();
+        *grid.get_mut(index(last, last, 0)) = 1;
+        *grid.get_mut(index(REGION_SIZE, last, 0)) = 2;
+        *grid.get_mut(index(last, REGION_SIZE, 0)) = 3;
+        assert_eq!(grid.region_count(), 3);
+        assert_eq!(grid.region(0).unwrap()[REGION_LEN - 1], 1);
+        assert_eq!(grid.region(1).unwrap()[(last * REGION_SIZE) as usize], 2);
+        assert_eq!(grid.region(HORIZONTAL_REGIONS).unwrap()[last as usize], 3);
+    }
+}
@@ -0,0 +1,66 @@
+use model::area::Area;
+use model::Coordinate;
+
+fn c(x: u16, y: u16, plane: u8) -> Coordinate {
+    Coordinate { x, y, plane }
+}
+
+fn tiles(area: &Area) -> Vec<Coordinate> {
+    let mut tiles = Vec::new();
+    area.for_each_tile(&mut |c| tiles.push(c));
+    tiles
+}
+
+#[test]
+fn rectangle_spans_planes() {
+    let area = Area::Rectangle { min: c(10, 20, 0), max: c(12, 21, 1) };
+    assert!(area.contains(&c(10, 20, 0)));
+    assert!(area.contains(&c(12, 21, 1)));
+    assert!(!area.contains(&c(13, 21, 1)));
+    assert!(!area.contains(&c(12, 21, 2)));
+    assert_eq!(tiles(&area).len(), 3 * 2 * 2);
+    assert!(tiles(&area).iter().all(|t| area.contains(t)));
+}
+
+#[test]
+fn polygon_includes_outline() {
+    // Right triangle with the right angle at (0, 0)
+    let area = Area::Polygon { points: vec![[0, 0], [4, 0], [0, 4]], planes: vec![0, 2] };
+    assert!(area.contains(&c(0, 0, 0)));
+    assert!(area.contains(&c(4, 0, 2)));
+    assert!(area.contains(&c(2, 2, 0)));
+    assert!(area.contains(&c(1, 1, 0)));
+    assert!(!area.contains(&c(3, 2, 0)));
+    assert!(!area.contains(&c(1, 1, 1)));
+    // 5 + 4 + 3 + 2 + 1 tiles per plane
+    assert_eq!(tiles(&area).len(), 15 * 2);
+    assert_eq!(area.bounds(), (c(0, 0, 0), c(4, 4, 2)));
+}
+
+#[test]
+fn concave_polygon() {
+    // U shape opening to the top
+    let area = Area::Polygon { points: vec![[0, 0], [6, 0], [6, 6], [4, 6], [4, 2], [2, 2], [2, 6], [0, 6]], planes: vec![0] };
+    assert!(area.contains(&c(1, 5, 0)));
+    assert!(area.contains(&c(5, 5, 0)));
+    assert!(area.contains(&c(3, 1, 0)));
+    assert!(area.contains(&c(3, 2, 0)));
+    assert!(!area.contains(&c(3, 3, 0)));
+    assert!(!area.contains(&c(3, 6, 0)));
+}
+
+#[test]
+fn union_and_validation() {
+    let area = Area::Union { areas: vec![
+        Area::Rectangle { min: c(0, 0, 0), max: c(1, 1, 0) },
+        Area::Polygon { points: vec![[5, 5]], planes: vec![3] },
+    ] };
+    assert!(area.contains(&c(1, 0, 0)));
+    assert!(area.contains(&c(5, 5, 3)));
+    assert!(!area.contains(&c(5, 5, 0)));
+    assert!(area.validate());
+    assert_eq!(area.bounds(), (c(0, 0, 0), c(5, 5, 3)));
+    assert!(!Area::Rectangle { min: c(2, 0, 0), max: c(1, 1, 0) }.validate());
+    assert!(!Area::Polygon { points: vec![[0, 0]], planes: vec![4] }.validate());
+    assert!(!Area::Union { areas: vec![] }.validate());
+}
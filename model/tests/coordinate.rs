@@ -0,0 +1,74 @@
+use model::Coordinate;
+use model::constants::*;
+
+const COORDINATES: [Coordinate; 6] = [
+    Coordinate { x: 0, y: 0, plane: 0 },
+    Coordinate { x: 3213, y: 3427, plane: 0 },
+    Coordinate { x: 2885, y: 9797, plane: 0 },
+    Coordinate { x: 1234, y: 5678, plane: 2 },
+    Coordinate { x: 63, y: 64, plane: 1 },
+    Coordinate { x: WIDTH as u16 - 1, y: HEIGHT as u16 - 1, plane: PLANES as u8 - 1 },
+];
+
+#[test]
+fn index_round_trip() {
+    for c in COORDINATES {
+        assert_eq!(Coordinate::from_index(c.index()), c);
+    }
+}
+
+#[test]
+fn id_round_trip() {
+    for c in COORDINATES {
+        assert_eq!(Coordinate::from_id(c.id()), c);
+        assert_eq!(Coordinate::from_runemate_hash(c.runemate_hash()), c);
+        assert_eq!(c.runemate_hash() as u32, c.id());
+    }
+}
+
+#[test]
+fn runelite_packed_round_trip() {
+    for c in COORDINATES {
+        let packed = c.runelite_packed();
+        assert_eq!(Coordinate::from_runelite_packed(packed), c);
+        assert_eq!(Coordinate::from_index(Coordinate::from_runelite_packed(packed).index()), c);
+    }
+    assert_eq!(Coordinate { x: 3213, y: 3427, plane: 3 }.runelite_packed(), 3213 | 3427 << 15 | 3 << 30);
+}
+
+#[test]
+fn checked_derive() {
+    let c = Coordinate { x: 3213, y: 3427, plane: 0 };
+    assert_eq!(c.checked_derive(-13, 3, 1), Some(Coordinate { x: 3200, y: 3430, plane: 1 }));
+    assert_eq!(c.checked_derive(-13, 3, 1), Some(c.derive(-13, 3, 1)));
+    assert_eq!(c.checked_derive(0, 0, -1), None);
+    assert_eq!(Coordinate { x: 0, y: 5, plane: 0 }.checked_derive(-1, 0, 0), None);
+    assert_eq!(Coordinate { x: WIDTH as u16 - 1, y: 5, plane: 0 }.checked_derive(1, 0, 0), None);
+    assert_eq!(Coordinate { x: 5, y: 5, plane: PLANES as u8 - 1 }.checked_derive(0, 0, 1), None);
+}
+
+#[test]
+fn distances() {
+    let a = Coordinate { x: 3200, y: 3200, plane: 0 };
+    let b = Coordinate { x: 3203, y: 3196, plane: 1 };
+    assert_eq!(a.chebyshev_distance(&b), 4);
+    assert_eq!(a.manhattan_distance(&b), 7);
+    assert_eq!(a.euclidean_distance(&b), 5.0);
+    assert_eq!(b.chebyshev_distance(&a), 4);
+    assert_eq!(a.chebyshev_distance(&a), 0);
+}
+
+#[test]
+fn regions_and_chunks() {
+    let lumbridge = Coordinate { x: 3222, y: 3218, plane: 0 };
+    assert_eq!(lumbridge.region_id(), 12850);
+    assert_eq!((lumbridge.region_x(), lumbridge.region_y()), (50, 50));
+    assert_eq!(Coordinate::from_region_id(12850, 22, 18, 0), lumbridge);
+    assert_eq!((lumbridge.chunk_x(), lumbridge.chunk_y()), (402, 402));
+    assert_eq!(lumbridge.chunk_base(), Coordinate { x: 3216, y: 3216, plane: 0 });
+    for c in COORDINATES {
+        let local_x = (c.x % REGION_SIZE as u16) as u8;
+        let local_y = (c.y % REGION_SIZE as u16) as u8;
+        assert_eq!(Coordinate::from_region_id(c.region_id(), local_x, local_y, c.plane), c);
+    }
+}
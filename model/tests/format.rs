@@ -0,0 +1,104 @@
+use std::fs::File;
+use std::path::PathBuf;
+
+use model::{Coordinate, Edge, NavGrid};
+use model::definitions::EdgeDefinition;
+use model::format::{Encoding, FormatError, Header, map_nav_grid, open_nav_grid, read_nav_grid, write_nav_grid};
+
+/// Vertices in a few regions, including both sides of a region border, and a teleport
+fn nav_grid() -> NavGrid {
+    let mut nav_grid = NavGrid::new();
+    for (i, (x, y, plane)) in [(3200, 3200, 0), (3263, 3263, 0), (3264, 3263, 0), (2500, 9800, 0), (3200, 3200, 1)].into_iter().enumerate() {
+        let vertex = &mut nav_grid.vertices[Coordinate { x, y, plane }.index()];
+        vertex.flags = 0x10 + i as u8;
+        vertex.extra_edges_and_group = 3;
+    }
+    let destination = Coordinate { x: 3200, y: 3200, plane: 0 };
+    nav_grid.teleports.push(Edge { destination, cost: 5, definition: EdgeDefinition::SpellTeleport { spell: "Test".to_string() }, requirements: Vec::new() });
+    nav_grid
+}
+
+fn written(encoding: Encoding) -> Vec<u8> {
+    let nav_grid = nav_grid();
+    let header = Header { encoding, ..Header::new(&nav_grid, 217) };
+    let mut bytes = Vec::new();
+    write_nav_grid(&mut bytes, &nav_grid, &header).unwrap();
+    bytes
+}
+
+/// Writes the bytes to a file of the test's own, removed when dropped
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str, bytes: &[u8]) -> TempFile {
+        let path = std::env::temp_dir().join(format!("osrs-nav-{}-{}.bin", std::process::id(), name));
+        std::fs::write(&path, bytes).unwrap();
+        TempFile(path)
+    }
+
+    fn map(&self) -> Result<NavGrid, FormatError> {
+        map_nav_grid(&File::open(&self.0).unwrap()).map(|(_, nav_grid)| nav_grid)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+#[test]
+fn round_trip() {
+    for encoding in [Encoding::Gzip, Encoding::Raw] {
+        let (header, _) = read_nav_grid(&written(encoding)[..]).unwrap();
+        assert_eq!(header.revision, 217);
+    }
+}
+
+#[test]
+fn checksum_covers_header() {
+    for encoding in [Encoding::Gzip, Encoding::Raw] {
+        let mut bytes = written(encoding);
+        // Lowest byte of the revision
+        bytes[8] ^= 1;
+        assert!(matches!(read_nav_grid(&bytes[..]), Err(FormatError::ChecksumMismatch { .. })));
+    }
+}
+
+#[test]
+fn trailing_data_is_rejected() {
+    for encoding in [Encoding::Gzip, Encoding::Raw] {
+        let mut bytes = written(encoding);
+        bytes.push(0);
+        assert!(matches!(read_nav_grid(&bytes[..]), Err(FormatError::TrailingData)));
+    }
+}
+
+#[test]
+fn mapped_vertices_equal_decoded_ones() {
+    let file = TempFile::new("mapped", &written(Encoding::Raw));
+    let (_, mapped) = open_nav_grid(file.0.as_path()).unwrap();
+    assert!(mapped.vertices.is_mapped());
+    let (_, decoded) = read_nav_grid(&written(Encoding::Gzip)[..]).unwrap();
+    assert!(!decoded.vertices.is_mapped());
+    assert_eq!(mapped.vertices.region_count(), 4);
+    assert_eq!(mapped.vertices.regions().collect::<Vec<_>>(), decoded.vertices.regions().collect::<Vec<_>>());
+    assert_eq!(mapped.vertices.regions().collect::<Vec<_>>(), nav_grid().vertices.regions().collect::<Vec<_>>());
+    assert_eq!(mapped.teleports.len(), 1);
+}
+
+#[test]
+fn mapped_checksum_mismatch_is_rejected() {
+    let mut bytes = written(Encoding::Raw);
+    // Within the slot table
+    bytes[100] ^= 1;
+    assert!(matches!(TempFile::new("corrupt", &bytes).map(), Err(FormatError::ChecksumMismatch { .. })));
+}
+
+#[test]
+fn truncated_mapped_file_is_rejected() {
+    let bytes = written(Encoding::Raw);
+    for len in [0, 39, 40, 1000, bytes.len() / 2, bytes.len() - 5, bytes.len() - 1] {
+        assert!(TempFile::new(&format!("truncated-{}", len), &bytes[..len]).map().is_err(), "{} bytes", len);
+    }
+}
@@ -0,0 +1,36 @@
+use model::definitions::{GameState, RequirementDefinition, Skill, WorldType};
+
+fn game_state(json: &str) -> GameState {
+    serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn membership_comes_from_world_types() {
+    let member = game_state(r#"{ "world_types": ["MEMBERS", "PVP"] }"#);
+    assert!(member.is_member());
+    assert!(RequirementDefinition::Membership.is_met(&member));
+    assert!(RequirementDefinition::WorldType { world_type: WorldType::Pvp }.is_met(&member));
+    let free = game_state(r#"{ "world_types": ["PVP"] }"#);
+    assert!(!RequirementDefinition::Membership.is_met(&free));
+    assert!(!RequirementDefinition::Membership.is_met(&game_state("{}")));
+}
+
+#[test]
+fn unknown_world_types_are_skipped() {
+    let game_state = game_state(r#"{ "world_types": ["MEMBERS", "SOME_NEW_TYPE"] }"#);
+    assert_eq!(game_state.world_types.len(), 1);
+    assert!(game_state.is_member());
+}
+
+#[test]
+fn unknown_skills_are_skipped() {
+    let game_state = game_state(r#"{ "skills": { "MAGIC": 25, "sailing": 10, "OVERALL": 50 } }"#);
+    assert_eq!(game_state.skills.len(), 1);
+    assert_eq!(game_state.skill_level(Skill::Magic), 25);
+}
+
+#[test]
+fn requirements_reject_unknown_skills() {
+    assert!(serde_json::from_str::<RequirementDefinition>(r#"{ "Skill": { "skill": "Sailing", "level": 10 } }"#).is_err());
+    assert!(serde_json::from_str::<RequirementDefinition>(r#"{ "Skill": { "skill": "magic", "level": 10 } }"#).is_ok());
+}
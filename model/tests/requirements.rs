@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+
+use model::definitions::{GameState, ItemContainer, Regex, RequirementDefinition, Skill};
+
+fn coins(quantity: u32) -> RequirementDefinition {
+    RequirementDefinition::Item { item: Regex::new("Coins").unwrap(), quantity, container: ItemContainer::Carried }
+}
+
+fn magic(level: u8) -> RequirementDefinition {
+    RequirementDefinition::Skill { skill: Skill::Magic, level }
+}
+
+fn game_state(coins: u32, magic: u8) -> GameState {
+    GameState {
+        inventory: HashMap::from([("Coins".to_string(), coins)]),
+        skills: HashMap::from([(Skill::Magic, magic)]),
+        ..GameState::default()
+    }
+}
+
+#[test]
+fn any_of_needs_one() {
+    let requirement = RequirementDefinition::AnyOf(vec![magic(25), coins(10)]);
+    assert!(requirement.is_met(&game_state(10, 1)));
+    assert!(requirement.is_met(&game_state(0, 25)));
+    assert!(!requirement.is_met(&game_state(9, 24)));
+    assert!(!RequirementDefinition::AnyOf(Vec::new()).is_met(&game_state(10, 25)));
+}
+
+#[test]
+fn all_of_needs_every_one() {
+    let requirement = RequirementDefinition::AllOf(vec![magic(25), coins(10)]);
+    assert!(requirement.is_met(&game_state(10, 25)));
+    assert!(!requirement.is_met(&game_state(10, 24)));
+    assert!(!requirement.is_met(&game_state(9, 25)));
+    assert!(RequirementDefinition::AllOf(Vec::new()).is_met(&game_state(0, 1)));
+}
+
+#[test]
+fn combinators_nest() {
+    // Either magic 25, or coins without a high magic level
+    let requirement = RequirementDefinition::AnyOf(vec![
+        magic(25),
+        RequirementDefinition::AllOf(vec![coins(10), RequirementDefinition::Not(Box::new(magic(10)))]),
+    ]);
+    assert!(requirement.is_met(&game_state(0, 25)));
+    assert!(requirement.is_met(&game_state(10, 9)));
+    assert!(!requirement.is_met(&game_state(10, 10)));
+    assert!(!requirement.is_met(&game_state(0, 9)));
+    let mut count = 0;
+    requirement.walk(&mut |_| count += 1);
+    assert_eq!(count, 6);
+}
@@ -0,0 +1,83 @@
+use std::collections::{HashMap, HashSet};
+
+use model::{Coordinate, NavGrid};
+use model::definitions::GameState;
+
+use crate::build_path;
+use crate::context::SearchContext;
+use crate::limits::{Limit, Limits};
+use crate::path::Path;
+use crate::weighted::{Targets, weighted_search};
+
+/// Percent the cost of moving onto a vertex grows by for each path found through it
+const PENALTY_PERCENT: u32 = 50;
+/// Searches per requested path before giving up on finding more distinct ones
+const ATTEMPTS_PER_PATH: usize = 4;
+
+#[derive(Clone, Debug)]
+pub struct AlternativesResult {
+    /// Distinct paths ordered by cost, the first is the cheapest path
+    pub paths: Vec<Path>,
+    /// Vertices visited by all searches
+    pub visited: usize,
+    /// Highest memory usage of a search in bytes
+    pub mem_usage: usize,
+    /// Limit a search was stopped by before finding a path
+    pub stopped: Option<Limit>,
+}
+
+/// Up to `count` alternative paths from the start to the end by the penalty method. After each search the vertices of
+/// the path found get more expensive to move onto, so the next search is pushed off it. A path is only kept if at most
+/// `max_overlap` of its tiles, as a fraction of the shorter of both paths, are shared with each path kept before. Paths
+/// cost what their edges cost, the penalties only steer the searches, though `max_cost` limits the penalized costs.
+pub fn alternatives(nav_grid: &NavGrid, start: &Coordinate, end: &Coordinate, count: usize, max_overlap: f64, game_state: &GameState, limits: &Limits) -> AlternativesResult {
+    let end_index = end.index();
+    let mut target_groups = [false; 128];
+    target_groups[nav_grid.vertices[end_index].get_group() as usize] = true;
+    let mut uses: HashMap<u32, u32> = HashMap::new();
+    let mut kept: Vec<(Path, HashSet<u32>)> = Vec::new();
+    let mut result = AlternativesResult { paths: Vec::new(), visited: 0, mem_usage: 0, stopped: None };
+    for _ in 0..count * ATTEMPTS_PER_PATH {
+        if kept.len() == count {
+            break;
+        }
+        let (steps, visited, mem_usage, stopped) = SearchContext::with(nav_grid, |context| {
+            let targets = Targets { groups: &target_groups, settle: |index, _| index == end_index };
+            let (reached, visited, stopped) = weighted_search(context, nav_grid, start, targets, |_, destination, edge| {
+                // Rounded up, so the penalty isn't lost on steps
+                let percent = 100 + uses.get(&destination).copied().unwrap_or(0) * PENALTY_PERCENT;
+                Some((edge.map_or(1, |edge| edge.cost) as f64 * percent as f64 / 100.0).ceil() as u32)
+            }, game_state, limits);
+            (reached.map(|index| build_path(nav_grid, &context.cache, start.index(), index)), visited, context.cache.mem_usage(), stopped)
+        });
+        result.visited += visited;
+        result.mem_usage = result.mem_usage.max(mem_usage);
+        result.stopped = stopped;
+        let Some(mut steps) = steps else {
+            break;
+        };
+        // Back from the penalized costs to the edges' own
+        let mut cost = 0;
+        for step in &mut steps {
+            cost += step.edge.cost;
+            step.cost = cost;
+        }
+        let tiles: HashSet<u32> = steps.iter().map(|step| step.position.index()).collect();
+        tiles.iter().for_each(|tile| *uses.entry(*tile).or_default() += 1);
+        if kept.iter().all(|(_, other)| overlap(&tiles, other) <= max_overlap) {
+            kept.push((Path::new(*start, steps), tiles));
+        }
+    }
+    result.paths = kept.into_iter().map(|(path, _)| path).collect();
+    result.paths.sort_by_key(|path| path.cost);
+    result
+}
+
+/// Fraction of the tiles of the smaller set that are in both, paths without steps overlap entirely
+fn overlap(a: &HashSet<u32>, b: &HashSet<u32>) -> f64 {
+    let (smaller, larger) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    if smaller.is_empty() {
+        return 1.0;
+    }
+    smaller.intersection(larger).count() as f64 / smaller.len() as f64
+}
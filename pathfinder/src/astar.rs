@@ -0,0 +1,93 @@
+use std::cmp::Reverse;
+
+use model::{Coordinate, NavGrid};
+use model::constants::*;
+use model::definitions::GameState;
+
+use crate::{build_path, EdgeRef};
+use crate::context::SearchContext;
+use crate::limits::Limits;
+use crate::path::{Path, SearchResult};
+
+/// A* search using the landmarks of the NavGrid as heuristic. The heuristic is admissible, so paths cost the same as
+/// with dijkstra while visiting fewer vertices. Without landmarks, or for vertices outside of their core, it degrades
+/// to dijkstra.
+pub fn astar(nav_grid: &NavGrid, start: &Coordinate, end: &Coordinate, game_state: &GameState, limits: &Limits) -> SearchResult {
+    SearchContext::with(nav_grid, |context| astar_in(context, nav_grid, start, end, game_state, limits))
+}
+
+fn astar_in(context: &mut SearchContext, nav_grid: &NavGrid, start: &Coordinate, end: &Coordinate, game_state: &GameState, limits: &Limits) -> SearchResult {
+    let start_index = start.index();
+    let end_index = end.index();
+    let target_group = nav_grid.vertices[end_index].get_group();
+    let heuristic = |index: u32| nav_grid.landmarks.estimate(index, end_index);
+    let SearchContext { heap: queue, cache, .. } = context;
+    let mut count = 0;
+    if nav_grid.vertices[start_index].get_group() == target_group {
+        cache.get_mut(start_index).cost = 0;
+        queue.push(Reverse((heuristic(start_index), 0, start_index)));
+    }
+    for (i, teleport) in nav_grid.teleports.iter().enumerate() {
+        if teleport.requirements.iter().all(|req| req.is_met(game_state)) {
+            let index = teleport.destination.index();
+            if nav_grid.vertices[index].get_group() == target_group {
+                let dest = cache.get_mut(index);
+                if teleport.cost < dest.cost {
+                    dest.cost = teleport.cost;
+                    dest.prev = start_index;
+                    dest.edge = EdgeRef::teleport(i);
+                    queue.push(Reverse((teleport.cost.saturating_add(heuristic(index)), teleport.cost, index)));
+                }
+            }
+        }
+    }
+
+    while let Some(Reverse((estimate, cost, index))) = queue.pop() {
+        if cost > cache.get_mut(index).cost {
+            continue; // Superseded by a cheaper entry
+        }
+        count += 1;
+        if let Some(limit) = limits.check(estimate, count) {
+            return SearchResult { path: None, visited: count, mem_usage: cache.mem_usage(), stopped: Some(limit) };
+        }
+        if index == end_index {
+            let path = Path::new(*start, build_path(nav_grid, cache, start_index, index));
+            return SearchResult { path: Some(path), visited: count, mem_usage: cache.mem_usage(), stopped: None };
+        }
+        // Bounds are per chunk and drop at chunk borders, carrying over the remaining estimate (pathmax) saves
+        // expanding vertices again
+        let remaining = estimate - cost;
+        let priority = |adj_index: u32, adj_cost: u32| {
+            adj_cost.saturating_add(heuristic(adj_index).max(remaining.saturating_sub(adj_cost - cost)))
+        };
+        let v = &nav_grid.vertices[index];
+        for (flag, dx, dy) in &DIRECTIONS {
+            if (v.flags & flag) != 0 {
+                let adj_index = (index as i32 + (WIDTH as i32 * *dy) + *dx) as u32;
+                let adj = cache.get_mut(adj_index);
+                if cost + 1 < adj.cost {
+                    adj.cost = cost + 1;
+                    adj.prev = index;
+                    adj.edge = EdgeRef::STEP;
+                    queue.push(Reverse((priority(adj_index, adj.cost), adj.cost, adj_index)));
+                }
+            }
+        }
+        if v.has_extra_edges() {
+            for (i, edge) in nav_grid.edges.get_vec(&index).unwrap().iter().enumerate() {
+                if edge.requirements.iter().all(|req| req.is_met(game_state)) {
+                    let adj_index = edge.destination.index();
+                    let adj = cache.get_mut(adj_index);
+                    if cost + edge.cost < adj.cost {
+                        adj.cost = cost + edge.cost;
+                        adj.prev = index;
+                        adj.edge = EdgeRef::extra(i);
+                        queue.push(Reverse((priority(adj_index, adj.cost), adj.cost, adj_index)));
+                    }
+                }
+            }
+        }
+    }
+
+    SearchResult { path: None, visited: count, mem_usage: cache.mem_usage(), stopped: None }
+}
@@ -0,0 +1,149 @@
+use model::{Coordinate, NavGrid};
+use model::constants::*;
+use model::definitions::GameState;
+
+use crate::{build_path, EdgeRef};
+use crate::context::SearchContext;
+use crate::limits::{Limit, Limits};
+use crate::path::{Path, PathStep, SearchResult};
+
+/// Dijkstra searching forward from the start and backward from the end at once, expanding whichever side has the
+/// lower cost so far, until the costs of both sides add up to the best path found between them. Teleports are only
+/// taken from the start, so they only seed the forward side.
+pub fn bidirectional_dijkstra(nav_grid: &NavGrid, start: &Coordinate, end: &Coordinate, game_state: &GameState, limits: &Limits) -> SearchResult {
+    SearchContext::with(nav_grid, |context| bidirectional_in(context, nav_grid, start, end, game_state, limits))
+}
+
+fn bidirectional_in(context: &mut SearchContext, nav_grid: &NavGrid, start: &Coordinate, end: &Coordinate, game_state: &GameState, limits: &Limits) -> SearchResult {
+    let start_index = start.index();
+    let end_index = end.index();
+    let target_group = nav_grid.vertices[end_index].get_group();
+    let SearchContext { queue: forward_queue, cache: forward, backward_queue, backward_cache: backward, .. } = context;
+    let mut count = 0;
+    if nav_grid.vertices[start_index].get_group() == target_group {
+        forward.get_mut(start_index).cost = 0;
+        forward_queue.push(0, (0, start_index));
+    }
+    for (i, teleport) in nav_grid.teleports.iter().enumerate() {
+        if teleport.requirements.iter().all(|req| req.is_met(game_state)) {
+            let index = teleport.destination.index();
+            if nav_grid.vertices[index].get_group() == target_group {
+                let dest = forward.get_mut(index);
+                if teleport.cost < dest.cost {
+                    dest.cost = teleport.cost;
+                    dest.prev = start_index;
+                    dest.edge = EdgeRef::teleport(i);
+                    forward_queue.push(teleport.cost, (dest.cost, index));
+                }
+            }
+        }
+    }
+    backward.get_mut(end_index).cost = 0;
+    backward_queue.push(0, (0, end_index));
+
+    // Cheapest path found so far and the vertex where its sides meet
+    let mut best = forward.get(end_index).map_or(u32::MAX, |state| state.cost);
+    let mut meeting = end_index;
+    while let (Some(&(forward_cost, _)), Some(&(backward_cost, _))) = (forward_queue.peek(), backward_queue.peek()) {
+        if forward_cost.saturating_add(backward_cost) >= best {
+            break;
+        }
+        count += 1;
+        if let Some(limit) = limits.check(forward_cost + backward_cost, count) {
+            let mem_usage = forward.mem_usage() + backward.mem_usage();
+            return SearchResult { path: None, visited: count, mem_usage, stopped: Some(limit) };
+        }
+        if forward_cost <= backward_cost {
+            let (cost, index) = forward_queue.pop().unwrap();
+            if cost > forward.get_mut(index).cost {
+                continue;
+            }
+            let mut relax = |adj_index: u32, edge_cost: u32, edge| {
+                let adj = forward.get_mut(adj_index);
+                if cost + edge_cost < adj.cost {
+                    adj.cost = cost + edge_cost;
+                    adj.prev = index;
+                    adj.edge = edge;
+                    forward_queue.push(edge_cost, (adj.cost, adj_index));
+                    if let Some(other) = backward.get(adj_index).filter(|other| other.cost != u32::MAX) {
+                        if cost + edge_cost + other.cost < best {
+                            best = cost + edge_cost + other.cost;
+                            meeting = adj_index;
+                        }
+                    }
+                }
+            };
+            let v = &nav_grid.vertices[index];
+            for (flag, dx, dy) in &DIRECTIONS {
+                if (v.flags & flag) != 0 {
+                    relax((index as i32 + (WIDTH as i32 * *dy) + *dx) as u32, 1, EdgeRef::STEP);
+                }
+            }
+            if v.has_extra_edges() {
+                for (i, edge) in nav_grid.edges.get_vec(&index).unwrap().iter().enumerate() {
+                    if edge.requirements.iter().all(|req| req.is_met(game_state)) {
+                        relax(edge.destination.index(), edge.cost, EdgeRef::extra(i));
+                    }
+                }
+            }
+        } else {
+            let (cost, index) = backward_queue.pop().unwrap();
+            if cost > backward.get_mut(index).cost {
+                continue;
+            }
+            // Prev points towards the end, edge leads from the vertex to prev
+            let mut relax = |adj_index: u32, edge_cost: u32, edge| {
+                let adj = backward.get_mut(adj_index);
+                if cost + edge_cost < adj.cost {
+                    adj.cost = cost + edge_cost;
+                    adj.prev = index;
+                    adj.edge = edge;
+                    backward_queue.push(edge_cost, (adj.cost, adj_index));
+                    if let Some(other) = forward.get(adj_index).filter(|other| other.cost != u32::MAX) {
+                        if cost + edge_cost + other.cost < best {
+                            best = cost + edge_cost + other.cost;
+                            meeting = adj_index;
+                        }
+                    }
+                }
+            };
+            let coordinate = Coordinate::from_index(index);
+            for (flag, dx, dy) in &DIRECTIONS {
+                if let Some(adj) = coordinate.checked_derive(-*dx as i16, -*dy as i16, 0) {
+                    if (nav_grid.vertices[adj.index()].flags & flag) != 0 {
+                        relax(adj.index(), 1, EdgeRef::STEP);
+                    }
+                }
+            }
+            for (source, i, edge) in nav_grid.edges_to(index) {
+                if edge.requirements.iter().all(|req| req.is_met(game_state)) {
+                    relax(source, edge.cost, EdgeRef::extra(i));
+                }
+            }
+        }
+    }
+
+    let mem_usage = forward.mem_usage() + backward.mem_usage();
+    if best == u32::MAX {
+        return SearchResult { path: None, visited: count, mem_usage, stopped: None };
+    }
+    if matches!(limits.max_cost, Some(max_cost) if best > max_cost) {
+        // Found once a side ran out of vertices, without the frontier passing the limit
+        return SearchResult { path: None, visited: count, mem_usage, stopped: Some(Limit::Cost) };
+    }
+    let mut path = build_path(nav_grid, forward, start_index, meeting);
+    let mut index = meeting;
+    while index != end_index {
+        let state = backward.get(index).unwrap();
+        // Backward costs count down to the end, the remainder of the best path
+        let cost = best - backward.get(state.prev).unwrap().cost;
+        // The edge leads from the vertex towards the end
+        if let Some(edge) = state.edge.resolve(nav_grid, index) {
+            path.push(PathStep { position: edge.destination, cost, edge: edge.clone() });
+        } else {
+            path.push(PathStep::walk(Coordinate::from_index(state.prev), cost));
+        }
+        index = state.prev;
+    }
+    SearchResult { path: Some(Path::new(*start, path)), visited: count, mem_usage, stopped: None }
+}
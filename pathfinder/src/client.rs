@@ -0,0 +1,126 @@
+use std::collections::VecDeque;
+
+use model::{Coordinate, NavGrid};
+use model::constants::DIRECTIONS;
+
+/// Tiles along each axis of the scene the client loads around the player
+pub const SCENE_SIZE: u16 = 104;
+/// Tiles around an unreachable destination the client looks for the closest reachable tile in
+const ALTERNATIVE_RADIUS: i32 = 10;
+/// Order the client's breadth first search tries the neighbours of a tile in
+const CLIENT_DIRECTIONS: [(i32, i32); 8] = [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)];
+
+/// South-west tile of the scene loaded around the position, 13 by 13 chunks centered on the position's chunk. The
+/// client only rebuilds the scene once the player gets close to its border, this is the freshly built scene.
+pub fn scene_base(position: &Coordinate) -> Coordinate {
+    Coordinate { x: position.chunk_x().saturating_sub(6) * 8, y: position.chunk_y().saturating_sub(6) * 8, plane: position.plane }
+}
+
+/// Routes the game client walks from a tile to each tile of the scene when clicking it. The client searches breadth
+/// first over single tile steps within the scene, trying neighbours in a fixed order, so of equally long routes it
+/// walks the one found first. Moves allowed by the vertex flags are the moves the client allows.
+pub struct ClientRoutes {
+    origin: Coordinate,
+    base: Coordinate,
+    /// Direction in `CLIENT_DIRECTIONS` plus one each tile was reached by, zero if unreached
+    via: Vec<u8>,
+    /// Steps to each tile
+    distances: Vec<u16>,
+}
+
+impl ClientRoutes {
+    pub fn new(nav_grid: &NavGrid, origin: &Coordinate) -> ClientRoutes {
+        let base = scene_base(origin);
+        let mut routes = ClientRoutes {
+            origin: *origin,
+            base,
+            via: vec![0; SCENE_SIZE as usize * SCENE_SIZE as usize],
+            distances: vec![u16::MAX; SCENE_SIZE as usize * SCENE_SIZE as usize],
+        };
+        let Some(origin_local) = routes.local(origin) else {
+            return routes;
+        };
+        routes.distances[origin_local] = 0;
+        let mut queue = VecDeque::from([*origin]);
+        while let Some(tile) = queue.pop_front() {
+            let flags = nav_grid.vertices[tile.index()].flags;
+            let distance = routes.distances[routes.local(&tile).unwrap()];
+            for (direction, (dx, dy)) in CLIENT_DIRECTIONS.iter().enumerate() {
+                if flags & direction_flag(*dx, *dy) == 0 {
+                    continue;
+                }
+                let Some(next) = tile.checked_derive(*dx as i16, *dy as i16, 0) else {
+                    continue;
+                };
+                if let Some(local) = routes.local(&next) {
+                    if routes.distances[local] == u16::MAX {
+                        routes.distances[local] = distance + 1;
+                        routes.via[local] = direction as u8 + 1;
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        routes
+    }
+
+    pub fn origin(&self) -> Coordinate {
+        self.origin
+    }
+
+    /// Index of the tile in the scene
+    fn local(&self, tile: &Coordinate) -> Option<usize> {
+        let (x, y) = (tile.x.wrapping_sub(self.base.x), tile.y.wrapping_sub(self.base.y));
+        (tile.plane == self.base.plane && x < SCENE_SIZE && y < SCENE_SIZE).then_some(y as usize * SCENE_SIZE as usize + x as usize)
+    }
+
+    /// Number of steps the client takes to the tile
+    pub fn distance(&self, tile: &Coordinate) -> Option<u16> {
+        self.local(tile).map(|local| self.distances[local]).filter(|distance| *distance != u16::MAX)
+    }
+
+    /// Tile the client walks to the tile from
+    pub fn previous(&self, tile: &Coordinate) -> Option<Coordinate> {
+        let via = self.via[self.local(tile)?];
+        let (dx, dy) = CLIENT_DIRECTIONS[via.checked_sub(1)? as usize];
+        Some(tile.derive(-dx as i16, -dy as i16, 0))
+    }
+
+    /// Tiles the client walks when clicking the destination, excluding the origin. Unreachable destinations are
+    /// substituted like the client does, by the reachable tile within 10 tiles closest to them, of those the one with
+    /// the shortest route. None if there is no such tile.
+    pub fn route(&self, destination: &Coordinate) -> Option<Vec<Coordinate>> {
+        let end = if self.distance(destination).is_some() { *destination } else { self.alternative(destination)? };
+        let mut route = vec![end];
+        let mut tile = end;
+        while let Some(previous) = self.previous(&tile) {
+            route.push(previous);
+            tile = previous;
+        }
+        route.pop();
+        route.reverse();
+        Some(route)
+    }
+
+    fn alternative(&self, destination: &Coordinate) -> Option<Coordinate> {
+        let mut best: Option<(i32, u16, Coordinate)> = None;
+        for dy in -ALTERNATIVE_RADIUS..=ALTERNATIVE_RADIUS {
+            for dx in -ALTERNATIVE_RADIUS..=ALTERNATIVE_RADIUS {
+                let Some(tile) = destination.checked_derive(dx as i16, dy as i16, 0) else {
+                    continue;
+                };
+                if let Some(distance) = self.distance(&tile) {
+                    let candidate = (dx * dx + dy * dy, distance, tile);
+                    if !matches!(best, Some(best) if (best.0, best.1) <= (candidate.0, candidate.1)) {
+                        best = Some(candidate);
+                    }
+                }
+            }
+        }
+        best.map(|(_, _, tile)| tile)
+    }
+}
+
+fn direction_flag(dx: i32, dy: i32) -> u8 {
+    DIRECTIONS.iter().find(|(_, x, y)| *x == dx && *y == dy).unwrap().0
+}
@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+
+use model::{Coordinate, Edge, NavGrid};
+use model::constants::DIRECTIONS;
+use model::definitions::EdgeDefinition;
+
+use crate::client::ClientRoutes;
+use crate::path::PathStep;
+
+/// How walks are merged into waypoints
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Waypoints {
+    /// Waypoints the straight line to is walkable from the previous one
+    #[default]
+    Line,
+    /// Waypoints the client's route finder walks the path to from the previous one
+    Client,
+}
+
+/// Whether the tiles of the straight line between both tiles can be walked one after another
+pub fn walkable_line(nav_grid: &NavGrid, from: &Coordinate, to: &Coordinate) -> bool {
+    if from.plane != to.plane {
+        return false;
+    }
+    let (dx, dy) = (to.x as i32 - from.x as i32, to.y as i32 - from.y as i32);
+    let steps = dx.abs().max(dy.abs());
+    let mut current = *from;
+    for i in 1..=steps {
+        // Tile of the line after i steps along the longer axis, rounded to the nearest
+        let x = from.x as i32 + (2 * dx * i + steps).div_euclid(2 * steps);
+        let y = from.y as i32 + (2 * dy * i + steps).div_euclid(2 * steps);
+        let (step_x, step_y) = (x - current.x as i32, y - current.y as i32);
+        let (flag, _, _) = DIRECTIONS.iter().find(|(_, dx, dy)| *dx == step_x && *dy == step_y).unwrap();
+        if nav_grid.vertices[current.index()].flags & flag == 0 {
+            return false;
+        }
+        current = current.derive(step_x as i16, step_y as i16, 0);
+    }
+    true
+}
+
+/// Merges runs of walking steps into waypoints at most `max_distance` tiles apart, other edges stay as they are. A
+/// waypoint is only placed beyond the previous one as long as the path hasn't left the distance yet. The steps' costs
+/// continue from `start_cost` at the start, they may be weighted and differ from their edges' costs.
+pub(crate) fn compress_steps(nav_grid: &NavGrid, start: (Coordinate, u32), steps: &[PathStep], max_distance: u32, waypoints: Waypoints) -> Vec<PathStep> {
+    match waypoints {
+        Waypoints::Line => line_steps(nav_grid, start, steps, max_distance),
+        Waypoints::Client => client_steps(nav_grid, start, steps, max_distance),
+    }
+}
+
+fn line_steps(nav_grid: &NavGrid, start: (Coordinate, u32), steps: &[PathStep], max_distance: u32) -> Vec<PathStep> {
+    merge_walks(start, steps, |anchor, walk| {
+        let mut next = 0;
+        for (i, step) in walk.iter().enumerate().skip(1) {
+            if anchor.chebyshev_distance(&step.position) > max_distance {
+                break;
+            }
+            if walkable_line(nav_grid, &anchor, &step.position) {
+                next = i;
+            }
+        }
+        next
+    })
+}
+
+/// Clicking each waypoint makes the client walk exactly the tiles of the path to it, see [ClientRoutes]
+fn client_steps(nav_grid: &NavGrid, start: (Coordinate, u32), steps: &[PathStep], max_distance: u32) -> Vec<PathStep> {
+    merge_walks(start, steps, |anchor, walk| {
+        let routes = ClientRoutes::new(nav_grid, &anchor);
+        let mut previous = anchor;
+        let mut next = 0;
+        for (i, step) in walk.iter().enumerate() {
+            if (i > 0 && anchor.chebyshev_distance(&step.position) > max_distance)
+                || routes.previous(&step.position) != Some(previous) {
+                break;
+            }
+            next = i;
+            previous = step.position;
+        }
+        next
+    })
+}
+
+/// Replaces each run of walking steps by the waypoints `next_waypoint` picks, given the position walked from and the
+/// rest of the run it returns the index of the next waypoint in the run.
+fn merge_walks<F>(start: (Coordinate, u32), steps: &[PathStep], mut next_waypoint: F) -> Vec<PathStep> where F: FnMut(Coordinate, &[PathStep]) -> usize {
+    let mut compressed = Vec::new();
+    let mut anchor = start;
+    let mut i = 0;
+    while i < steps.len() {
+        if !steps[i].is_walk() {
+            compressed.push(steps[i].clone());
+            anchor = (steps[i].position, steps[i].cost);
+            i += 1;
+            continue;
+        }
+        let walk_end = steps[i..].iter().position(|step| !step.is_walk()).map_or(steps.len(), |end| i + end);
+        // The first step of the run is always a valid waypoint, it was walked to
+        let next = i + next_waypoint(anchor.0, &steps[i..walk_end]);
+        let step = &steps[next];
+        let edge = Edge { destination: step.position, cost: step.cost - anchor.1, definition: EdgeDefinition::Step { position: step.position }, requirements: Vec::new() };
+        compressed.push(PathStep { position: step.position, cost: step.cost, edge });
+        anchor = (step.position, step.cost);
+        i = next + 1;
+    }
+    compressed
+}
@@ -0,0 +1,59 @@
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use model::NavGrid;
+use model::util::RegionCache;
+
+use crate::{BucketRingBuffer, DijkstraCacheState};
+
+thread_local! {
+    static CONTEXT: RefCell<Option<SearchContext>> = const { RefCell::new(None) };
+}
+
+/// Queues and caches of a search, kept per thread so searches don't allocate and box regions over and over
+pub(crate) struct SearchContext {
+    pub queue: BucketRingBuffer<(u32, u32)>,
+    pub cache: RegionCache<DijkstraCacheState>,
+    /// Backward side of the bidirectional search
+    pub backward_queue: BucketRingBuffer<(u32, u32)>,
+    pub backward_cache: RegionCache<DijkstraCacheState>,
+    /// Queue of A*, entries are (estimate, cost, index)
+    pub heap: BinaryHeap<Reverse<(u32, u32, u32)>>,
+}
+
+impl SearchContext {
+    fn new(nav_grid: &NavGrid) -> SearchContext {
+        SearchContext {
+            queue: BucketRingBuffer::new(nav_grid.max_cost()),
+            cache: RegionCache::new(DijkstraCacheState::default()),
+            backward_queue: BucketRingBuffer::new(nav_grid.max_cost()),
+            backward_cache: RegionCache::new(DijkstraCacheState::default()),
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    fn reset(&mut self, nav_grid: &NavGrid) {
+        if self.queue.buckets.len() != nav_grid.max_cost() as usize + 1 {
+            *self = SearchContext::new(nav_grid);
+            return;
+        }
+        self.queue.reset();
+        self.cache.reset();
+        self.backward_queue.reset();
+        self.backward_cache.reset();
+        self.heap.clear();
+    }
+
+    /// Runs the search with the reset context of the current thread, nested searches get a fresh one
+    pub fn with<R, F>(nav_grid: &NavGrid, search: F) -> R where F: FnOnce(&mut SearchContext) -> R {
+        CONTEXT.with(|context| match context.try_borrow_mut() {
+            Ok(mut context) => {
+                let context = context.get_or_insert_with(|| SearchContext::new(nav_grid));
+                context.reset(nav_grid);
+                search(context)
+            }
+            Err(_) => search(&mut SearchContext::new(nav_grid)),
+        })
+    }
+}
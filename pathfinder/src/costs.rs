@@ -0,0 +1,39 @@
+use model::Edge;
+
+use crate::profile::CostProfile;
+use crate::zones::{Zone, zone_cost};
+
+/// Changes a single search makes to the costs of moves, the profile rescales edges and the zones then apply to the
+/// tiles moved onto
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Costs<'a> {
+    pub zones: &'a [Zone],
+    pub profile: Option<&'a CostProfile>,
+}
+
+impl Costs<'static> {
+    /// Costs of the NavGrid as they are
+    pub const NONE: Costs<'static> = Costs { zones: &[], profile: None };
+}
+
+impl Costs<'_> {
+    pub fn is_none(&self) -> bool {
+        self.zones.is_empty() && self.profile.is_none()
+    }
+
+    /// Cost of a move onto the vertex by the edge, none for steps. None if the move is not to be taken.
+    pub(crate) fn cost(&self, destination: u32, edge: Option<&Edge>) -> Option<u32> {
+        let cost = match self.profile {
+            Some(profile) => profile.cost(edge),
+            None => edge.map_or(1, |edge| edge.cost),
+        };
+        zone_cost(self.zones, destination, cost)
+    }
+}
+
+/// Cost multiplied by the factor and rounded to the nearest integer, though at least 1 unless either is 0. Moves only
+/// become free when asked to, not by rounding.
+pub(crate) fn scale(cost: u32, factor: f64) -> u32 {
+    let scaled = (cost as f64 * factor).round() as u32;
+    if cost > 0 && factor > 0.0 { scaled.max(1) } else { scaled }
+}
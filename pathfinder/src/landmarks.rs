@@ -0,0 +1,107 @@
+use model::{Coordinate, NavGrid};
+use model::constants::*;
+use model::landmarks::{Bounds, Landmark, Landmarks, REGION_CHUNKS, region_chunk};
+use model::util::{REGION_COUNT, REGION_LEN, RegionCache, vertex_index};
+
+use crate::BucketRingBuffer;
+
+/// Computes landmarks at the given positions, see [Landmarks]. The core is made of the vertices that can reach and be
+/// reached from the first position, positions outside of it yield landmarks without bounds.
+pub fn compute_landmarks(nav_grid: &NavGrid, positions: &[Coordinate]) -> Landmarks {
+    let max_cost = nav_grid.iter_edges().map(|edge| edge.cost).max().unwrap_or(1);
+
+    let mut core = vec![Vec::new(); REGION_COUNT];
+    let mut landmarks = Vec::new();
+    for (i, position) in positions.iter().enumerate() {
+        let from = costs(nav_grid, position, false, max_cost);
+        let to = costs(nav_grid, position, true, max_cost);
+        let cost = |cache: &RegionCache<u32>, index| cache.get(index).copied().unwrap_or(u32::MAX);
+        if i == 0 {
+            for (region, _) in nav_grid.vertices.regions() {
+                let bits: Vec<u64> = (0..REGION_LEN / 64).map(|word| (0..64).fold(0, |bits, bit| {
+                    let index = vertex_index(region, word * 64 + bit);
+                    let connected = cost(&from, index) != u32::MAX && cost(&to, index) != u32::MAX;
+                    bits | (connected as u64) << bit
+                })).collect();
+                if bits.iter().any(|word| *word != 0) {
+                    core[region as usize] = bits;
+                }
+            }
+        }
+        let mut landmark = Landmark { position: *position, bounds: vec![Vec::new(); REGION_COUNT] };
+        for (region, bits) in core.iter().enumerate().filter(|(_, bits)| !bits.is_empty()) {
+            let mut bounds = vec![Bounds { from_min: u32::MAX, from_max: 0, to_min: u32::MAX, to_max: 0 }; REGION_CHUNKS];
+            for offset in (0..REGION_LEN).filter(|offset| bits[offset / 64] & (1 << (offset % 64)) != 0) {
+                let index = vertex_index(region as u32, offset);
+                let (from_cost, to_cost) = (cost(&from, index), cost(&to, index));
+                let chunk = &mut bounds[region_chunk(offset)];
+                chunk.from_min = chunk.from_min.min(from_cost);
+                chunk.from_max = chunk.from_max.max(from_cost);
+                chunk.to_min = chunk.to_min.min(to_cost);
+                chunk.to_max = chunk.to_max.max(to_cost);
+            }
+            // Chunks without core vertices have no bounds
+            for chunk in &mut bounds {
+                if chunk.from_min == u32::MAX {
+                    chunk.from_max = u32::MAX;
+                }
+                if chunk.to_min == u32::MAX {
+                    chunk.to_max = u32::MAX;
+                }
+            }
+            landmark.bounds[region] = bounds;
+        }
+        landmarks.push(landmark);
+    }
+    Landmarks::new(core, landmarks)
+}
+
+/// Cost of every vertex from the position, or to the position if reverse
+fn costs(nav_grid: &NavGrid, position: &Coordinate, reverse: bool, max_cost: u32) -> RegionCache<u32> {
+    let mut queue = BucketRingBuffer::new(max_cost);
+    let mut cache = RegionCache::new(u32::MAX);
+    *cache.get_mut(position.index()) = 0;
+    queue.push(0, (0, position.index()));
+
+    while let Some(current) = queue.next_bin() {
+        while let Some((cost, index)) = queue.buckets[current].pop() {
+            if cost > *cache.get_mut(index) {
+                continue;
+            }
+            let mut relax = |adj_index: u32, edge_cost: u32| {
+                let adj = cache.get_mut(adj_index);
+                if cost + edge_cost < *adj {
+                    *adj = cost + edge_cost;
+                    queue.push(edge_cost, (*adj, adj_index));
+                }
+            };
+            if reverse {
+                let coordinate = Coordinate::from_index(index);
+                for (flag, dx, dy) in &DIRECTIONS {
+                    if let Some(adj) = coordinate.checked_derive(-*dx as i16, -*dy as i16, 0) {
+                        if (nav_grid.vertices[adj.index()].flags & flag) != 0 {
+                            relax(adj.index(), 1);
+                        }
+                    }
+                }
+                for (source, _, edge) in nav_grid.edges_to(index) {
+                    relax(source, edge.cost);
+                }
+            } else {
+                let v = &nav_grid.vertices[index];
+                for (flag, dx, dy) in &DIRECTIONS {
+                    if (v.flags & flag) != 0 {
+                        relax((index as i32 + (WIDTH as i32 * *dy) + *dx) as u32, 1);
+                    }
+                }
+                if v.has_extra_edges() {
+                    for edge in nav_grid.edges.get_vec(&index).unwrap() {
+                        relax(edge.destination.index(), edge.cost);
+                    }
+                }
+            }
+        }
+        queue.increment();
+    }
+    cache
+}
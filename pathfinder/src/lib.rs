@@ -1,15 +1,73 @@
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 
 use model::{Coordinate, Edge, NavGrid};
+use model::area::Area;
 use model::constants::*;
-use model::definitions::{EdgeDefinition, GameState};
-use model::util::RegionCache;
+use model::definitions::GameState;
+use model::util::{region_index, RegionCache};
+
+pub use crate::astar::astar;
+pub use crate::bidirectional::bidirectional_dijkstra;
+use crate::context::SearchContext;
+use crate::limits::{Limit, Limits};
+use crate::path::{Path, PathStep, SearchResult};
+use crate::costs::Costs;
+use crate::weighted::{Targets, weighted_search};
+
+pub mod alternatives;
+mod astar;
+mod bidirectional;
+pub mod client;
+pub mod compress;
+mod context;
+pub mod costs;
+pub mod landmarks;
+pub mod limits;
+pub mod matrix;
+pub mod path;
+pub mod planner;
+pub mod profile;
+pub mod route;
+mod weighted;
+pub mod zones;
 
 #[derive(Clone, Copy)]
-struct DijkstraCacheState<'a> {
+struct DijkstraCacheState {
     cost: u32,
     prev: u32,
-    edge: Option<&'a Edge>,
+    edge: EdgeRef,
+}
+
+impl Default for DijkstraCacheState {
+    fn default() -> DijkstraCacheState {
+        DijkstraCacheState { cost: u32::MAX, prev: u32::MAX, edge: EdgeRef::STEP }
+    }
+}
+
+/// Edge taken between two vertices by its position among the edges of its source vertex, or among the teleports.
+/// Holding no reference lets cache states outlive the NavGrid borrow of a single search.
+#[derive(Clone, Copy, Eq, PartialEq)]
+struct EdgeRef(u32);
+
+impl EdgeRef {
+    const STEP: EdgeRef = EdgeRef(u32::MAX);
+    const TELEPORT: u32 = 1 << 31;
+
+    fn extra(i: usize) -> EdgeRef {
+        EdgeRef(i as u32)
+    }
+
+    fn teleport(i: usize) -> EdgeRef {
+        EdgeRef(i as u32 | EdgeRef::TELEPORT)
+    }
+
+    fn resolve(self, nav_grid: &NavGrid, source: u32) -> Option<&Edge> {
+        match self {
+            EdgeRef::STEP => None,
+            EdgeRef(i) if i & EdgeRef::TELEPORT != 0 => Some(&nav_grid.teleports[(i & !EdgeRef::TELEPORT) as usize]),
+            EdgeRef(i) => Some(&nav_grid.edges.get_vec(&source).unwrap()[i as usize]),
+        }
+    }
 }
 
 pub struct BucketRingBuffer<T> {
@@ -45,12 +103,25 @@ impl<T: Clone> BucketRingBuffer<T> {
                 index -= len;
             }
             if !self.buckets[index].is_empty() {
+                // Costs are pushed relative to the cursor, skipped buckets must not offset them
+                self.cursor = index;
                 return Some(index);
             }
         }
         None
     }
 
+    /// Removes a state of the lowest cost
+    fn pop(&mut self) -> Option<T> {
+        let bin = self.next_bin()?;
+        self.buckets[bin].pop()
+    }
+
+    fn peek(&mut self) -> Option<&T> {
+        let bin = self.next_bin()?;
+        self.buckets[bin].last()
+    }
+
     fn push(&mut self, cost: u32, state: T) {
         let len = self.buckets.len();
         let mut index = cost as usize + self.cursor;
@@ -61,27 +132,101 @@ impl<T: Clone> BucketRingBuffer<T> {
     }
 }
 
-pub fn dijkstra(nav_grid: &NavGrid, start: &Coordinate, end: &Coordinate, game_state: &GameState) -> (usize, usize, Option<Vec<EdgeDefinition>>) {
+/// Path from the start to the end. Costs may rescale or block moves, the path's costs include the rescaling.
+pub fn dijkstra(nav_grid: &NavGrid, start: &Coordinate, end: &Coordinate, costs: &Costs, game_state: &GameState, limits: &Limits) -> SearchResult {
+    dijkstra_nearest(nav_grid, start, std::slice::from_ref(end), costs, game_state, limits)
+}
+
+/// Path to whichever of the ends is the cheapest to reach, the path's end tells which one
+pub fn dijkstra_nearest(nav_grid: &NavGrid, start: &Coordinate, ends: &[Coordinate], costs: &Costs, game_state: &GameState, limits: &Limits) -> SearchResult {
+    let end_indices: HashSet<u32> = ends.iter().map(Coordinate::index).collect();
+    let mut target_groups = [false; 128];
+    for index in &end_indices {
+        target_groups[nav_grid.vertices[*index].get_group() as usize] = true;
+    }
+    SearchContext::with(nav_grid, |context| {
+        let targets = Targets { groups: &target_groups, settle: |index, _| end_indices.contains(&index) };
+        dijkstra_until(context, nav_grid, start, targets, costs, game_state, limits)
+    })
+}
+
+/// Path to the cheapest to reach tile of the area, the path's end tells which one
+pub fn dijkstra_area(nav_grid: &NavGrid, start: &Coordinate, area: &Area, costs: &Costs, game_state: &GameState, limits: &Limits) -> SearchResult {
+    let target_groups = area_groups(nav_grid, area);
+    SearchContext::with(nav_grid, |context| {
+        let targets = Targets { groups: &target_groups, settle: |index, _| area.contains(&Coordinate::from_index(index)) };
+        dijkstra_until(context, nav_grid, start, targets, costs, game_state, limits)
+    })
+}
+
+/// Groups of the vertices within the area's bounds. Regions never written to share a single vertex and are looked up
+/// once each, so the work is bounded by the allocated regions rather than by the area's size.
+fn area_groups(nav_grid: &NavGrid, area: &Area) -> [bool; 128] {
+    let mut groups = [false; 128];
+    let (min, max) = area.bounds();
+    let last = REGION_SIZE as u16 - 1;
+    for plane in min.plane..=max.plane {
+        for region_y in min.region_y()..=max.region_y() {
+            for region_x in min.region_x()..=max.region_x() {
+                let base = Coordinate::from_map_square(region_x as u8, region_y as u8, 0, 0, plane);
+                if nav_grid.vertices.region(region_index(base.index())).is_none() {
+                    groups[nav_grid.vertices[base.index()].get_group() as usize] = true;
+                    continue;
+                }
+                for y in min.y.max(base.y)..=max.y.min(base.y + last) {
+                    for x in min.x.max(base.x)..=max.x.min(base.x + last) {
+                        groups[nav_grid.vertices[Coordinate { x, y, plane }.index()].get_group() as usize] = true;
+                    }
+                }
+            }
+        }
+    }
+    groups
+}
+
+/// Searches until a target vertex is reached. Unchanged costs are those of the edges and the buckets suffice,
+/// changed costs may exceed them.
+fn dijkstra_until<F>(context: &mut SearchContext, nav_grid: &NavGrid, start: &Coordinate, targets: Targets<F>, costs: &Costs, game_state: &GameState, limits: &Limits) -> SearchResult where F: FnMut(u32, u32) -> bool {
+    let (reached, visited, stopped) = if costs.is_none() {
+        dijkstra_search(context, nav_grid, start, targets.groups, targets.settle, game_state, limits)
+    } else {
+        weighted_search(context, nav_grid, start, targets, |_, destination, edge| costs.cost(destination, edge), game_state, limits)
+    };
+    let path = reached.map(|index| {
+        let mut steps = build_path(nav_grid, &context.cache, start.index(), index);
+        if !costs.is_none() {
+            // Edges report the cost their step took, not their cost in the NavGrid
+            let mut previous = 0;
+            for step in &mut steps {
+                step.edge.cost = step.cost - previous;
+                previous = step.cost;
+            }
+        }
+        Path::new(*start, steps)
+    });
+    SearchResult { path, visited, mem_usage: context.cache.mem_usage(), stopped }
+}
+
+/// Settles vertices in order of their cost until `settle` returns true for one, which is returned along with the
+/// visited count and the limit the search was stopped by. Vertices may be settled more than once, the first time
+/// with their final cost.
+pub(crate) fn dijkstra_search<F>(context: &mut SearchContext, nav_grid: &NavGrid, start: &Coordinate, target_groups: &[bool; 128], mut settle: F, game_state: &GameState, limits: &Limits) -> (Option<u32>, usize, Option<Limit>) where F: FnMut(u32, u32) -> bool {
     let start_index = start.index();
-    let end_index = end.index();
-    let target_group = nav_grid.vertices[end_index as usize].get_group();
-    let max_cost = nav_grid.iter_edges().map(|edge| edge.cost).max().unwrap();
-    let mut queue = BucketRingBuffer::new(max_cost); //TODO borrow from pool instead to prevent allocations?
-    let mut cache = RegionCache::new(DijkstraCacheState { cost: u32::MAX, prev: u32::MAX, edge: None });
+    let SearchContext { queue, cache, .. } = context;
     let mut count = 0;
-    if nav_grid.vertices[start_index as usize].get_group() == target_group {
+    if target_groups[nav_grid.vertices[start_index].get_group() as usize] {
         cache.get_mut(start_index).cost = 0;
         queue.push(0, (0, start_index));
     }
-    for teleport in &nav_grid.teleports {
+    for (i, teleport) in nav_grid.teleports.iter().enumerate() {
         if teleport.requirements.iter().all(|req| req.is_met(game_state)) {
             let index = teleport.destination.index();
-            if nav_grid.vertices[index as usize].get_group() == target_group {
+            if target_groups[nav_grid.vertices[index].get_group() as usize] {
                 let dest = cache.get_mut(index);
                 if teleport.cost < dest.cost {
                     dest.cost = teleport.cost;
                     dest.prev = start_index;
-                    dest.edge = Some(teleport);
+                    dest.edge = EdgeRef::teleport(i);
                     queue.push(teleport.cost, (dest.cost, index));
                 }
             }
@@ -89,23 +234,15 @@ pub fn dijkstra(nav_grid: &NavGrid, start: &Coordinate, end: &Coordinate, game_s
     }
 
     while let Some(current) = queue.next_bin() {
-        while let Some((cost, mut index)) = queue.buckets[current].pop() {
+        while let Some((cost, index)) = queue.buckets[current].pop() {
             count += 1;
-            if index == end_index {
-                let mut path = vec![];
-                while index != start_index {
-                    let state = cache.get_mut(index);
-                    if let Some(edge) = state.edge {
-                        path.push(edge.definition.clone());
-                    } else {
-                        path.push(EdgeDefinition::Step { position: Coordinate::from_index(index) });
-                    }
-                    index = state.prev;
-                }
-                path.reverse();
-                return (count, cache.mem_usage(), Some(path));
+            if let Some(limit) = limits.check(cost, count) {
+                return (None, count, Some(limit));
             }
-            let v = &nav_grid.vertices[index as usize];
+            if settle(index, cost) {
+                return (Some(index), count, None);
+            }
+            let v = &nav_grid.vertices[index];
             for (flag, dx, dy) in &DIRECTIONS {
                 if (v.flags & flag) != 0 {
                     let adj_index = (index as i32 + (WIDTH as i32 * *dy) + *dx) as u32;
@@ -113,19 +250,19 @@ pub fn dijkstra(nav_grid: &NavGrid, start: &Coordinate, end: &Coordinate, game_s
                     if cost + 1 < adj.cost {
                         adj.cost = cost + 1;
                         adj.prev = index;
-                        adj.edge = None;
+                        adj.edge = EdgeRef::STEP;
                         queue.push(1, (adj.cost, adj_index));
                     }
                 }
             }
             if v.has_extra_edges() {
-                for edge in nav_grid.edges.get_vec(&index).unwrap() {
+                for (i, edge) in nav_grid.edges.get_vec(&index).unwrap().iter().enumerate() {
                     if edge.requirements.iter().all(|req| req.is_met(game_state)) {
                         let adj = cache.get_mut(edge.destination.index());
                         if cost + edge.cost < adj.cost {
                             adj.cost = cost + edge.cost;
                             adj.prev = index;
-                            adj.edge = Some(edge);
+                            adj.edge = EdgeRef::extra(i);
                             queue.push(edge.cost, (adj.cost, edge.destination.index()));
                         }
                     }
@@ -135,7 +272,22 @@ pub fn dijkstra(nav_grid: &NavGrid, start: &Coordinate, end: &Coordinate, game_s
         queue.increment();
     }
 
-    (count, cache.mem_usage(), None)
+    (None, count, None)
+}
+
+fn build_path(nav_grid: &NavGrid, cache: &RegionCache<DijkstraCacheState>, start_index: u32, mut index: u32) -> Vec<PathStep> {
+    let mut path = vec![];
+    while index != start_index {
+        let state = cache.get(index).unwrap();
+        if let Some(edge) = state.edge.resolve(nav_grid, state.prev) {
+            path.push(PathStep { position: edge.destination, cost: state.cost, edge: edge.clone() });
+        } else {
+            path.push(PathStep::walk(Coordinate::from_index(index), state.cost));
+        }
+        index = state.prev;
+    }
+    path.reverse();
+    path
 }
 
 pub fn flood<F>(nav_grid: &NavGrid, start: &Coordinate, mut visit_vertex: F) where F: FnMut(u32) -> bool {
@@ -144,7 +296,7 @@ pub fn flood<F>(nav_grid: &NavGrid, start: &Coordinate, mut visit_vertex: F) whe
     queue.push_back(start.index());
     *cache.get_mut(start.index()) = true;
     while let Some(index) = queue.pop_front() {
-        let v = &nav_grid.vertices[index as usize];
+        let v = &nav_grid.vertices[index];
         if !visit_vertex(index) {
             continue;
         }
@@ -169,3 +321,24 @@ pub fn flood<F>(nav_grid: &NavGrid, start: &Coordinate, mut visit_vertex: F) whe
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queue_cursor_follows_skipped_buckets() {
+        let mut queue = BucketRingBuffer::new(5);
+        // Nothing at cost 0, the first pop skips to bucket 3
+        queue.push(3, 'a');
+        queue.push(5, 'b');
+        assert_eq!(queue.pop(), Some('a'));
+        // Pushed relative to cost 3, a stale cursor would queue them relative to 0 and pop 'e' at cost 6 before 'b'
+        queue.push(1, 'c');
+        assert_eq!(queue.pop(), Some('c'));
+        queue.push(2, 'e');
+        assert_eq!(queue.pop(), Some('b'));
+        assert_eq!(queue.pop(), Some('e'));
+        assert_eq!(queue.pop(), None);
+    }
+}
@@ -0,0 +1,69 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+/// Vertices visited between checks of the clock and the cancellation token
+const CHECK_INTERVAL: usize = 256;
+
+/// Budget of a single search, unset limits don't apply
+#[derive(Clone, Debug, Default)]
+pub struct Limits {
+    /// Highest path cost to search up to, inclusive
+    pub max_cost: Option<u32>,
+    pub max_visited: Option<usize>,
+    pub deadline: Option<Instant>,
+    pub cancellation: Option<CancellationToken>,
+}
+
+/// Limit that stopped a search before it found a path or ran out of vertices
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Limit {
+    Cost,
+    Visited,
+    Deadline,
+    Cancelled,
+}
+
+/// Stops searches holding a clone of the token, searches check it cooperatively every few hundred vertices
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        Default::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl Limits {
+    pub fn none() -> Limits {
+        Default::default()
+    }
+
+    /// Limit exceeded once the search visits a vertex that far along, cost being a lower bound of any path still
+    /// to be found
+    pub(crate) fn check(&self, cost: u32, visited: usize) -> Option<Limit> {
+        if matches!(self.max_cost, Some(max_cost) if cost > max_cost) {
+            Some(Limit::Cost)
+        } else if matches!(self.max_visited, Some(max_visited) if visited > max_visited) {
+            Some(Limit::Visited)
+        } else if visited % CHECK_INTERVAL != 0 {
+            None
+        } else if matches!(self.deadline, Some(deadline) if Instant::now() >= deadline) {
+            Some(Limit::Deadline)
+        } else if matches!(&self.cancellation, Some(token) if token.is_cancelled()) {
+            Some(Limit::Cancelled)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use std::collections::HashMap;
+
+use model::{Coordinate, NavGrid};
+use model::definitions::GameState;
+
+use crate::context::SearchContext;
+use crate::dijkstra_search;
+use crate::limits::{Limit, Limits};
+
+#[derive(Clone, Debug)]
+pub struct CostsResult {
+    /// Cost to each target in the order requested, none if unreachable or beyond a limit
+    pub costs: Vec<Option<u32>>,
+    pub visited: usize,
+    pub mem_usage: usize,
+    /// Limit the search was stopped by before reaching all targets
+    pub stopped: Option<Limit>,
+}
+
+/// Costs from the start to each of the targets with a single dijkstra search, which ends once every reachable
+/// target is settled. Targets may repeat.
+pub fn dijkstra_costs(nav_grid: &NavGrid, start: &Coordinate, targets: &[Coordinate], game_state: &GameState, limits: &Limits) -> CostsResult {
+    let mut positions: HashMap<u32, Vec<usize>> = HashMap::new();
+    let mut target_groups = [false; 128];
+    for (i, target) in targets.iter().enumerate() {
+        positions.entry(target.index()).or_default().push(i);
+        target_groups[nav_grid.vertices[target.index()].get_group() as usize] = true;
+    }
+    let mut costs = vec![None; targets.len()];
+    let mut remaining = positions.len();
+    SearchContext::with(nav_grid, |context| {
+        let (_, visited, stopped) = dijkstra_search(context, nav_grid, start, &target_groups, |index, cost| {
+            if let Some(positions) = positions.remove(&index) {
+                positions.into_iter().for_each(|i| costs[i] = Some(cost));
+                remaining -= 1;
+            }
+            remaining == 0
+        }, game_state, limits);
+        CostsResult { costs, visited, mem_usage: context.cache.mem_usage(), stopped }
+    })
+}
+
+/// Costs from each origin to each target, one search per origin
+pub fn cost_matrix(nav_grid: &NavGrid, origins: &[Coordinate], targets: &[Coordinate], game_state: &GameState, limits: &Limits) -> Vec<CostsResult> {
+    origins.iter().map(|origin| dijkstra_costs(nav_grid, origin, targets, game_state, limits)).collect()
+}
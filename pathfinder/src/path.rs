@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+
+use model::{Coordinate, Edge, NavGrid};
+use model::definitions::EdgeDefinition;
+
+use crate::compress::{compress_steps, Waypoints};
+use crate::limits::Limit;
+
+/// Outcome of a search along with statistics for comparing searches
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    pub path: Option<Path>,
+    /// Number of vertices taken from the queue
+    pub visited: usize,
+    /// Memory used by the search state in bytes
+    pub mem_usage: usize,
+    /// Limit the search was stopped by before finding a path
+    pub stopped: Option<Limit>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Path {
+    pub start: Coordinate,
+    /// Tile the path ends on, one of possibly many ends
+    pub end: Coordinate,
+    pub cost: u32,
+    pub steps: Vec<PathStep>,
+    pub segments: Vec<Segment>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PathStep {
+    /// Tile reached by the step
+    pub position: Coordinate,
+    /// Cost from the start up to and including the step
+    pub cost: u32,
+    /// Edge taken, walking a tile is a Step edge. Its cost is what the step took, rescaled by zones and profiles like
+    /// the step's cost, so it may differ from the edge's cost in the NavGrid.
+    pub edge: Edge,
+}
+
+/// Consecutive steps of the same kind, `steps[start..end]` of the path
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Segment {
+    pub kind: SegmentKind,
+    pub start: usize,
+    pub end: usize,
+    /// Cost of the segment's steps
+    pub cost: u32,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum SegmentKind {
+    /// Walking tile by tile
+    Walk,
+    /// A single door, object interaction or teleport
+    Action,
+}
+
+impl PathStep {
+    pub(crate) fn walk(position: Coordinate, cost: u32) -> PathStep {
+        let edge = Edge { destination: position, cost: 1, definition: EdgeDefinition::Step { position }, requirements: Vec::new() };
+        PathStep { position, cost, edge }
+    }
+
+    pub fn is_walk(&self) -> bool {
+        matches!(self.edge.definition, EdgeDefinition::Step { .. })
+    }
+}
+
+impl Path {
+    pub fn new(start: Coordinate, steps: Vec<PathStep>) -> Path {
+        let end = steps.last().map_or(start, |step| step.position);
+        let cost = steps.last().map_or(0, |step| step.cost);
+        let mut segments: Vec<Segment> = Vec::new();
+        for (i, step) in steps.iter().enumerate() {
+            let kind = if step.is_walk() { SegmentKind::Walk } else { SegmentKind::Action };
+            let step_cost = step.cost - if i == 0 { 0 } else { steps[i - 1].cost };
+            match segments.last_mut() {
+                Some(segment) if kind == SegmentKind::Walk && segment.kind == SegmentKind::Walk => {
+                    segment.end += 1;
+                    segment.cost += step_cost;
+                }
+                _ => segments.push(Segment { kind, start: i, end: i + 1, cost: step_cost }),
+            }
+        }
+        Path { start, end, cost, steps, segments }
+    }
+
+    /// Path with its walks merged into waypoints at most `max_distance` tiles apart, e.g. to click on the minimap.
+    /// Waypoints are only kept if walking from the previous one follows the path, see [Waypoints].
+    pub fn compress(&self, nav_grid: &NavGrid, max_distance: u32, waypoints: Waypoints) -> Path {
+        Path::new(self.start, compress_steps(nav_grid, (self.start, 0), &self.steps, max_distance, waypoints))
+    }
+
+    /// Definitions of the edges taken, the format paths used to be returned in
+    pub fn definitions(&self) -> Vec<EdgeDefinition> {
+        self.steps.iter().map(|step| step.edge.definition.clone()).collect()
+    }
+}
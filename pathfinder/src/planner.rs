@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+
+use model::{Coordinate, NavGrid};
+use model::definitions::GameState;
+
+use crate::costs::Costs;
+use crate::limits::{Limit, Limits};
+use crate::matrix::cost_matrix;
+use crate::route::{route, Route};
+
+/// Cost of unreachable pairs, high enough for any tour through them to cost more than all others
+const UNREACHABLE: u64 = u32::MAX as u64 * 1024;
+/// Most stops to try all orders of, 5040 orders
+const EXACT_STOPS: usize = 7;
+
+/// Stops in the order they are visited, as indices into the given stops, and the route through them
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Plan {
+    pub order: Vec<usize>,
+    pub route: Route,
+}
+
+#[derive(Clone, Debug)]
+pub struct PlanResult {
+    pub plan: Option<Plan>,
+    /// Vertices visited by the cost matrix and route searches
+    pub visited: usize,
+    /// Limit a search was stopped by, leaving some stops out of reach
+    pub stopped: Option<Limit>,
+}
+
+/// Picks a cheap order to visit the stops in, from the start and optionally ending at the end, then plans the route.
+/// Costs between all of them come from a cost matrix. Few stops are ordered by trying every order, more from nearest
+/// neighbour improved by 2-opt and moving single stops. Costs may differ by direction because of teleports and one
+/// way edges, so moves are rated by the cost of the whole tour.
+pub fn plan(nav_grid: &NavGrid, start: &Coordinate, stops: &[Coordinate], end: Option<&Coordinate>, game_state: &GameState, limits: &Limits) -> PlanResult {
+    // Matrix nodes are the start, the stops and the end if any
+    let nodes: Vec<Coordinate> = std::iter::once(*start).chain(stops.iter().copied()).chain(end.copied()).collect();
+    let results = cost_matrix(nav_grid, &nodes, &nodes, game_state, limits);
+    let mut visited = results.iter().map(|result| result.visited).sum();
+    let stopped = results.iter().find_map(|result| result.stopped);
+    let costs: Vec<Vec<u64>> = results.into_iter()
+        .map(|result| result.costs.into_iter().map(|cost| cost.map_or(UNREACHABLE, u64::from)).collect())
+        .collect();
+
+    let mut tour = nearest_neighbour(&costs, stops.len());
+    if end.is_some() {
+        tour.push(nodes.len() - 1);
+    }
+    if stops.len() <= EXACT_STOPS {
+        exhaustive(&costs, &mut tour, end.is_some());
+    } else {
+        two_opt(&costs, &mut tour, end.is_some());
+    }
+    if tour.windows(2).any(|leg| costs[leg[0]][leg[1]] == UNREACHABLE) {
+        return PlanResult { plan: None, visited, stopped };
+    }
+
+    let waypoints: Vec<Coordinate> = tour.iter().map(|node| nodes[*node]).collect();
+    let result = route(nav_grid, &waypoints, &Costs::NONE, game_state, limits);
+    visited += result.visited;
+    let order = tour[1..].iter().map(|node| node - 1).filter(|stop| *stop < stops.len()).collect();
+    PlanResult { plan: result.route.map(|route| Plan { order, route }), visited, stopped: result.stopped }
+}
+
+/// Tour from the start node 0 always going to the cheapest to reach stop not visited yet
+fn nearest_neighbour(costs: &[Vec<u64>], stops: usize) -> Vec<usize> {
+    let mut tour = vec![0];
+    let mut remaining: Vec<usize> = (1..=stops).collect();
+    while !remaining.is_empty() {
+        let current = tour[tour.len() - 1];
+        let (i, _) = remaining.iter().enumerate().min_by_key(|(_, node)| costs[current][**node]).unwrap();
+        tour.push(remaining.swap_remove(i));
+    }
+    tour
+}
+
+fn tour_cost(costs: &[Vec<u64>], tour: &[usize]) -> u64 {
+    tour.windows(2).map(|leg| costs[leg[0]][leg[1]]).sum()
+}
+
+/// Replaces the tour with the cheapest order of its stops, going through the permutations by Heap's algorithm
+fn exhaustive(costs: &[Vec<u64>], tour: &mut [usize], fixed_end: bool) {
+    let movable = tour.len() - usize::from(fixed_end);
+    let mut current = tour.to_vec();
+    let mut best = tour_cost(costs, tour);
+    let stops = movable - 1;
+    let mut counters = vec![0; stops];
+    let mut i = 0;
+    while i < stops {
+        if counters[i] < i {
+            let swap = if i % 2 == 0 { 0 } else { counters[i] };
+            current.swap(1 + swap, 1 + i);
+            let cost = tour_cost(costs, &current);
+            if cost < best {
+                best = cost;
+                tour.copy_from_slice(&current);
+            }
+            counters[i] += 1;
+            i = 0;
+        } else {
+            counters[i] = 0;
+            i += 1;
+        }
+    }
+}
+
+/// Reverses parts of the tour, or moves single stops elsewhere, as long as that makes it cheaper. The start and a
+/// fixed end stay in place.
+fn two_opt(costs: &[Vec<u64>], tour: &mut [usize], fixed_end: bool) {
+    let movable = tour.len() - usize::from(fixed_end);
+    let mut best = tour_cost(costs, tour);
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for i in 1..movable {
+            for j in i + 1..movable {
+                tour[i..=j].reverse();
+                let cost = tour_cost(costs, tour);
+                if cost < best {
+                    best = cost;
+                    improved = true;
+                } else {
+                    tour[i..=j].reverse();
+                }
+            }
+        }
+        // Reversing keeps the direction of no leg within the part, moving a stop makes up for it
+        for i in 1..movable {
+            for j in 1..movable {
+                if i == j {
+                    continue;
+                }
+                move_stop(tour, i, j);
+                let cost = tour_cost(costs, tour);
+                if cost < best {
+                    best = cost;
+                    improved = true;
+                } else {
+                    move_stop(tour, j, i);
+                }
+            }
+        }
+    }
+}
+
+fn move_stop(tour: &mut [usize], from: usize, to: usize) {
+    if from < to {
+        tour[from..=to].rotate_left(1);
+    } else {
+        tour[to..=from].rotate_right(1);
+    }
+}
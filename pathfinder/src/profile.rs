@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use model::Edge;
+use model::definitions::{EdgeKind, RequirementDefinition};
+
+use crate::costs::scale;
+
+/// Rescales the costs of edges for a single search, as different players value them differently, e.g. an ironman
+/// saving law runes or a speedrunner taking teleports at any price. Unknown fields are rejected, a misspelled one would
+/// otherwise leave costs silently unchanged.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CostProfile {
+    /// Multiplier of the cost of edges by their kind, `Step` scales walking. Kinds left out keep their cost.
+    #[serde(default)]
+    pub kinds: HashMap<EdgeKind, f32>,
+    /// Cost added per item an edge requires by item name, e.g. 20 per law rune
+    #[serde(default)]
+    pub items: HashMap<String, u32>,
+}
+
+impl CostProfile {
+    pub fn validate(&self) -> bool {
+        self.kinds.values().all(|multiplier| multiplier.is_finite() && *multiplier >= 0.0)
+    }
+
+    /// Cost of taking the edge, none for steps, rescaled as by [scale]
+    pub(crate) fn cost(&self, edge: Option<&Edge>) -> u32 {
+        let (kind, cost) = edge.map_or((EdgeKind::Step, 1), |edge| (edge.definition.kind(), edge.cost));
+        let scaled = match self.kinds.get(&kind) {
+            Some(multiplier) => scale(cost, *multiplier as f64),
+            None => cost,
+        };
+        let penalty = edge.map_or(0, |edge| edge.requirements.iter().map(|requirement| self.item_cost(requirement)).fold(0, u32::saturating_add));
+        scaled.saturating_add(penalty)
+    }
+
+    /// Cost of the items the requirement asks for, of alternatives the cheapest. Items required to be absent cost
+    /// nothing.
+    fn item_cost(&self, requirement: &RequirementDefinition) -> u32 {
+        match requirement {
+            RequirementDefinition::Item { item, quantity, .. } => self.items.iter()
+                .filter(|(name, _)| item.is_match(name))
+                .map(|(_, cost)| cost.saturating_mul(*quantity))
+                .max()
+                .unwrap_or(0),
+            RequirementDefinition::AllOf(requirements) => requirements.iter().map(|requirement| self.item_cost(requirement)).fold(0, u32::saturating_add),
+            RequirementDefinition::AnyOf(requirements) => requirements.iter().map(|requirement| self.item_cost(requirement)).min().unwrap_or(0),
+            _ => 0,
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use model::{Coordinate, NavGrid};
+use model::definitions::GameState;
+
+use crate::compress::{compress_steps, Waypoints};
+use crate::costs::Costs;
+use crate::dijkstra;
+use crate::limits::{Limit, Limits};
+use crate::path::{Path, PathStep};
+
+/// Path visiting waypoints in order, made of one leg per pair of consecutive waypoints
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Route {
+    pub path: Path,
+    pub legs: Vec<Leg>,
+}
+
+impl Route {
+    /// Route with the walks of each leg merged into waypoints, see [Path::compress]
+    pub fn compress(&self, nav_grid: &NavGrid, max_distance: u32, waypoints: Waypoints) -> Route {
+        let mut steps = Vec::new();
+        let mut legs = Vec::new();
+        for leg in &self.legs {
+            let start_cost = leg.steps.start.checked_sub(1).map_or(0, |i| self.path.steps[i].cost);
+            let compressed = compress_steps(nav_grid, (leg.start, start_cost), &self.path.steps[leg.steps.clone()], max_distance, waypoints);
+            legs.push(Leg { steps: steps.len()..steps.len() + compressed.len(), ..leg.clone() });
+            steps.extend(compressed);
+        }
+        Route { path: Path::new(self.path.start, steps), legs }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Leg {
+    pub start: Coordinate,
+    pub end: Coordinate,
+    /// Steps of the route's path making up the leg
+    pub steps: Range<usize>,
+    pub cost: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct RouteResult {
+    pub route: Option<Route>,
+    /// Vertices visited by all legs' searches
+    pub visited: usize,
+    /// Highest memory usage of a leg's search in bytes
+    pub mem_usage: usize,
+    /// Limit a leg's search was stopped by before finding a path
+    pub stopped: Option<Limit>,
+}
+
+/// Concatenates a dijkstra path for each leg, failing if any leg has none. Limits apply per leg, except the deadline.
+pub fn route(nav_grid: &NavGrid, waypoints: &[Coordinate], costs: &Costs, game_state: &GameState, limits: &Limits) -> RouteResult {
+    let mut steps = Vec::new();
+    let mut legs = Vec::new();
+    let mut result = RouteResult { route: None, visited: 0, mem_usage: 0, stopped: None };
+    for leg in waypoints.windows(2) {
+        let search = dijkstra(nav_grid, &leg[0], &leg[1], costs, game_state, limits);
+        result.visited += search.visited;
+        result.mem_usage = result.mem_usage.max(search.mem_usage);
+        result.stopped = search.stopped;
+        let Some(path) = search.path else {
+            return result;
+        };
+        let offset = steps.last().map_or(0, |step: &PathStep| step.cost);
+        legs.push(Leg { start: leg[0], end: leg[1], steps: steps.len()..steps.len() + path.steps.len(), cost: path.cost });
+        steps.extend(path.steps.into_iter().map(|mut step| {
+            step.cost += offset;
+            step
+        }));
+    }
+    result.route = Some(Route { path: Path::new(waypoints[0], steps), legs });
+    result
+}
@@ -0,0 +1,95 @@
+use std::cmp::Reverse;
+
+use model::{Coordinate, Edge, NavGrid};
+use model::constants::*;
+use model::definitions::GameState;
+
+use crate::EdgeRef;
+use crate::context::SearchContext;
+use crate::limits::{Limit, Limits};
+
+/// Vertices a search ends at once `settle` returns true for one, only vertices of the groups can lead there
+pub(crate) struct Targets<'a, F: FnMut(u32, u32) -> bool> {
+    pub groups: &'a [bool; 128],
+    pub settle: F,
+}
+
+/// Dijkstra search like [crate::dijkstra_search] with the cost of each move given by `weight`, from the move's source
+/// and destination vertex and its edge, none for steps. Moves weighted none are not taken. Weights may exceed the costs
+/// of the NavGrid's edges, so vertices are queued in the heap instead of the buckets.
+pub(crate) fn weighted_search<F, W>(context: &mut SearchContext, nav_grid: &NavGrid, start: &Coordinate, targets: Targets<F>, mut weight: W, game_state: &GameState, limits: &Limits) -> (Option<u32>, usize, Option<Limit>)
+    where F: FnMut(u32, u32) -> bool, W: FnMut(u32, u32, Option<&Edge>) -> Option<u32> {
+    let Targets { groups: target_groups, mut settle } = targets;
+    let start_index = start.index();
+    let SearchContext { heap: queue, cache, .. } = context;
+    let mut count = 0;
+    if target_groups[nav_grid.vertices[start_index].get_group() as usize] {
+        cache.get_mut(start_index).cost = 0;
+        queue.push(Reverse((0, 0, start_index)));
+    }
+    for (i, teleport) in nav_grid.teleports.iter().enumerate() {
+        if teleport.requirements.iter().all(|req| req.is_met(game_state)) {
+            let index = teleport.destination.index();
+            if target_groups[nav_grid.vertices[index].get_group() as usize] {
+                let Some(cost) = weight(start_index, index, Some(teleport)) else {
+                    continue;
+                };
+                let dest = cache.get_mut(index);
+                if cost < dest.cost {
+                    dest.cost = cost;
+                    dest.prev = start_index;
+                    dest.edge = EdgeRef::teleport(i);
+                    queue.push(Reverse((cost, cost, index)));
+                }
+            }
+        }
+    }
+
+    while let Some(Reverse((_, cost, index))) = queue.pop() {
+        if cost > cache.get_mut(index).cost {
+            continue; // Superseded by a cheaper entry
+        }
+        count += 1;
+        if let Some(limit) = limits.check(cost, count) {
+            return (None, count, Some(limit));
+        }
+        if settle(index, cost) {
+            return (Some(index), count, None);
+        }
+        let v = &nav_grid.vertices[index];
+        for (flag, dx, dy) in &DIRECTIONS {
+            if (v.flags & flag) != 0 {
+                let adj_index = (index as i32 + (WIDTH as i32 * *dy) + *dx) as u32;
+                let Some(step_cost) = weight(index, adj_index, None) else {
+                    continue;
+                };
+                let adj = cache.get_mut(adj_index);
+                if cost.saturating_add(step_cost) < adj.cost {
+                    adj.cost = cost + step_cost;
+                    adj.prev = index;
+                    adj.edge = EdgeRef::STEP;
+                    queue.push(Reverse((adj.cost, adj.cost, adj_index)));
+                }
+            }
+        }
+        if v.has_extra_edges() {
+            for (i, edge) in nav_grid.edges.get_vec(&index).unwrap().iter().enumerate() {
+                if edge.requirements.iter().all(|req| req.is_met(game_state)) {
+                    let adj_index = edge.destination.index();
+                    let Some(edge_cost) = weight(index, adj_index, Some(edge)) else {
+                        continue;
+                    };
+                    let adj = cache.get_mut(adj_index);
+                    if cost.saturating_add(edge_cost) < adj.cost {
+                        adj.cost = cost + edge_cost;
+                        adj.prev = index;
+                        adj.edge = EdgeRef::extra(i);
+                        queue.push(Reverse((adj.cost, adj.cost, adj_index)));
+                    }
+                }
+            }
+        }
+    }
+
+    (None, count, None)
+}
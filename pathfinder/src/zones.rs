@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+use model::Coordinate;
+use model::area::Area;
+
+use crate::costs::scale;
+
+/// Area a single search avoids or finds more expensive to move through, without changing the NavGrid
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Zone {
+    pub area: Area,
+    pub cost: ZoneCost,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum ZoneCost {
+    /// Moves into the area are never taken, e.g. the wilderness
+    Blocked,
+    /// Moves into the area cost this many times their cost, e.g. 3 for a swamp
+    Multiplier(f32),
+}
+
+impl Zone {
+    pub fn validate(&self) -> bool {
+        self.area.validate() && match self.cost {
+            ZoneCost::Blocked => true,
+            ZoneCost::Multiplier(multiplier) => multiplier.is_finite() && multiplier >= 0.0,
+        }
+    }
+}
+
+/// Cost of a move of the given cost onto the vertex, none if a zone blocks it. Multipliers of overlapping zones
+/// multiply, the cost is rescaled as by [scale].
+pub(crate) fn zone_cost(zones: &[Zone], destination: u32, cost: u32) -> Option<u32> {
+    let position = Coordinate::from_index(destination);
+    let mut factor = 1.0;
+    for zone in zones.iter().filter(|zone| zone.area.contains(&position)) {
+        match zone.cost {
+            ZoneCost::Blocked => return None,
+            ZoneCost::Multiplier(multiplier) => factor *= multiplier as f64,
+        }
+    }
+    Some(scale(cost, factor))
+}
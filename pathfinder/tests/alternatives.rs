@@ -0,0 +1,66 @@
+use std::collections::HashSet;
+
+use model::NavGrid;
+use model::definitions::GameState;
+use pathfinder::alternatives::alternatives;
+use pathfinder::costs::Costs;
+use pathfinder::dijkstra;
+use pathfinder::limits::{Limit, Limits};
+use pathfinder::path::Path;
+
+use crate::common::tile;
+
+mod common;
+
+/// Three corridors from (0, 0) to (10, 0) of 10, 14 and 18 steps, joined by the columns at both ends
+///
+/// ```text
+/// ...........
+/// .#########.
+/// ...........
+/// .#########.
+/// S.........E
+/// ```
+fn grid() -> NavGrid {
+    common::grid(11, 5, |x, y| y % 2 == 0 || x == 0 || x == 10)
+}
+
+fn tiles(path: &Path) -> HashSet<u32> {
+    path.steps.iter().map(|step| step.position.index()).collect()
+}
+
+#[test]
+fn penalty_pushes_second_path_into_next_corridor() {
+    let grid = grid();
+    let (start, end) = (tile(0, 0), tile(10, 0));
+    let cheapest = dijkstra(&grid, &start, &end, &Costs::NONE, &GameState::default(), &Limits::default()).path.unwrap();
+    let result = alternatives(&grid, &start, &end, 2, 0.5, &GameState::default(), &Limits::default());
+    assert_eq!(result.paths.len(), 2);
+    assert_eq!(tiles(&result.paths[0]), tiles(&cheapest));
+    assert_eq!(result.paths[0].cost, 10);
+    // Costs its own steps, not the penalized ones
+    assert_eq!(result.paths[1].cost, 14);
+    assert_eq!(result.paths[1].cost, result.paths[1].steps.iter().map(|step| step.edge.cost).sum::<u32>());
+    assert!(result.paths[1].steps.iter().any(|step| step.position == tile(5, 2)));
+    assert_eq!(tiles(&result.paths[0]).intersection(&tiles(&result.paths[1])).count(), 1);
+}
+
+#[test]
+fn max_overlap_rejects_paths_sharing_tiles() {
+    let grid = grid();
+    let (start, end) = (tile(0, 0), tile(10, 0));
+    // Every path shares the end
+    let strict = alternatives(&grid, &start, &end, 2, 0.0, &GameState::default(), &Limits::default());
+    assert_eq!(strict.paths.len(), 1);
+    let lenient = alternatives(&grid, &start, &end, 3, 1.0, &GameState::default(), &Limits::default());
+    assert_eq!(lenient.paths.len(), 3);
+    assert!(lenient.paths.windows(2).all(|pair| pair[0].cost <= pair[1].cost));
+}
+
+#[test]
+fn limits_stop_the_first_search() {
+    let limits = Limits { max_cost: Some(5), ..Limits::default() };
+    let result = alternatives(&grid(), &tile(0, 0), &tile(10, 0), 2, 0.5, &GameState::default(), &limits);
+    assert!(result.paths.is_empty());
+    assert_eq!(result.stopped, Some(Limit::Cost));
+}
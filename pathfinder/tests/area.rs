@@ -0,0 +1,51 @@
+use model::area::Area;
+use model::Coordinate;
+use model::constants::*;
+use model::definitions::GameState;
+use pathfinder::costs::Costs;
+use pathfinder::dijkstra_area;
+use pathfinder::limits::Limits;
+
+use crate::common::{grid, tile};
+
+mod common;
+
+fn rectangle(min: (i32, i32), max: (i32, i32)) -> Area {
+    Area::Rectangle { min: tile(min.0, min.1), max: tile(max.0, max.1) }
+}
+
+#[test]
+fn nearest_tile_of_area() {
+    let grid = grid(20, 20, |_, _| true);
+    let area = rectangle((10, 5), (12, 7));
+    let path = dijkstra_area(&grid, &tile(2, 6), &area, &Costs::NONE, &GameState::default(), &Limits::default()).path.unwrap();
+    // Any of the western edge's tiles
+    assert!(area.contains(&path.end));
+    assert_eq!(path.end.x, tile(10, 0).x);
+    assert_eq!(path.cost, 8);
+}
+
+#[test]
+fn area_of_other_group_is_not_searched() {
+    // Pocket at x >= 15 walled off by x = 14, its vertices in a group of their own
+    let mut grid = grid(20, 20, |x, _| x != 14);
+    for x in 15..20 {
+        for y in 0..20 {
+            grid.vertices[tile(x, y).index()].set_group(1);
+        }
+    }
+    let result = dijkstra_area(&grid, &tile(2, 6), &rectangle((16, 5), (18, 7)), &Costs::NONE, &GameState::default(), &Limits::default());
+    assert!(result.path.is_none());
+    assert_eq!(result.visited, 0);
+}
+
+#[test]
+fn whole_map_area() {
+    // Only the grid's own region is looked at tile by tile
+    let grid = grid(20, 20, |_, _| true);
+    let max = Coordinate { x: (WIDTH - 1) as u16, y: (HEIGHT - 1) as u16, plane: (PLANES - 1) as u8 };
+    let area = Area::Rectangle { min: Coordinate { x: 0, y: 0, plane: 0 }, max };
+    assert!(area.validate());
+    let result = dijkstra_area(&grid, &tile(2, 6), &area, &Costs::NONE, &GameState::default(), &Limits::default());
+    assert_eq!(result.path.unwrap().cost, 0);
+}
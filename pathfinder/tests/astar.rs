@@ -0,0 +1,102 @@
+use model::{Coordinate, Edge, NavGrid};
+use model::definitions::{EdgeDefinition, GameState, RequirementDefinition, Skill};
+use pathfinder::costs::Costs;
+use pathfinder::landmarks::compute_landmarks;
+use pathfinder::limits::Limits;
+use pathfinder::{astar, dijkstra};
+
+use crate::common::{grid, tile};
+
+mod common;
+
+/// Walls at x = 12 open to the north and at x = 25 open to the south, so paths across wind around both
+fn serpentine() -> NavGrid {
+    grid(40, 40, |x, y| !((x == 12 && y <= 30) || (x == 25 && y >= 10)))
+}
+
+/// Wall at x = 20 across the whole grid with a door through it at y = 20, taking 2
+fn walled() -> NavGrid {
+    let mut grid = grid(40, 40, |x, _| x != 20);
+    for (source, destination) in [(tile(19, 20), tile(21, 20)), (tile(21, 20), tile(19, 20))] {
+        let definition = EdgeDefinition::Door { id: 1, position: tile(20, 20), action: "Open".parse().unwrap() };
+        grid.edges.insert(source.index(), Edge { destination, cost: 2, definition, requirements: Vec::new() });
+        grid.vertices[source.index()].set_extra_edges(true);
+    }
+    grid
+}
+
+fn with_landmarks(mut nav_grid: NavGrid) -> NavGrid {
+    nav_grid.landmarks = compute_landmarks(&nav_grid, &[tile(0, 0), tile(39, 39), tile(39, 0), tile(0, 39)]);
+    nav_grid
+}
+
+/// A* finds paths as cheap as dijkstra's while visiting at most as many vertices
+fn assert_matches_dijkstra(nav_grid: &NavGrid, start: Coordinate, end: Coordinate, game_state: &GameState) -> u32 {
+    let expected = dijkstra(nav_grid, &start, &end, &Costs::NONE, game_state, &Limits::default());
+    let actual = astar(nav_grid, &start, &end, game_state, &Limits::default());
+    let (expected_path, actual_path) = (expected.path.unwrap(), actual.path.unwrap());
+    assert_eq!(actual_path.cost, expected_path.cost, "{:?} to {:?}", start, end);
+    assert_eq!(actual_path.end, end);
+    assert!(actual.visited <= expected.visited, "{:?} to {:?} visited {} > {}", start, end, actual.visited, expected.visited);
+    actual_path.cost
+}
+
+#[test]
+fn costs_match_dijkstra() {
+    let grid = with_landmarks(serpentine());
+    assert!(!grid.landmarks.is_empty());
+    let pairs = [((2, 2), (37, 37)), ((37, 2), (2, 37)), ((5, 35), (30, 5)), ((13, 0), (24, 39)), ((20, 20), (20, 20))];
+    for ((x1, y1), (x2, y2)) in pairs {
+        assert_matches_dijkstra(&grid, tile(x1, y1), tile(x2, y2), &GameState::default());
+    }
+}
+
+#[test]
+fn landmarks_let_astar_visit_fewer_vertices() {
+    let grid = with_landmarks(serpentine());
+    let (start, end) = (tile(2, 2), tile(37, 37));
+    let with = astar(&grid, &start, &end, &GameState::default(), &Limits::default());
+    let without = astar(&serpentine(), &start, &end, &GameState::default(), &Limits::default());
+    assert_eq!(with.path.unwrap().cost, without.path.unwrap().cost);
+    assert!(with.visited < without.visited);
+}
+
+#[test]
+fn teleport_cheaper_than_walking() {
+    let mut grid = serpentine();
+    let magic = RequirementDefinition::Skill { skill: Skill::Magic, level: 25 };
+    grid.teleports.push(Edge { destination: tile(36, 36), cost: 3, definition: EdgeDefinition::SpellTeleport { spell: "Test".to_string() }, requirements: vec![magic] });
+    let grid = with_landmarks(grid);
+    let mage = GameState { skills: [(Skill::Magic, 25)].into(), ..GameState::default() };
+    assert_eq!(assert_matches_dijkstra(&grid, tile(2, 2), tile(37, 37), &mage), 4);
+    // Without the level the teleport isn't taken
+    assert!(assert_matches_dijkstra(&grid, tile(2, 2), tile(37, 37), &GameState::default()) > 4);
+}
+
+#[test]
+fn extra_edges_are_taken() {
+    let grid = with_landmarks(walled());
+    let (start, end) = (tile(2, 20), tile(37, 20));
+    assert_eq!(assert_matches_dijkstra(&grid, start, end, &GameState::default()), 35);
+    let path = astar(&grid, &start, &end, &GameState::default(), &Limits::default()).path.unwrap();
+    assert!(path.steps.iter().any(|step| matches!(step.edge.definition, EdgeDefinition::Door { .. })));
+    assert_matches_dijkstra(&grid, tile(0, 0), tile(39, 39), &GameState::default());
+    assert_matches_dijkstra(&grid, tile(35, 2), tile(3, 38), &GameState::default());
+}
+
+#[test]
+fn estimates_are_lower_bounds() {
+    // Teleports are left out of the bounds, they are only taken from the start of a search
+    let grid = with_landmarks(walled());
+    for target in [tile(37, 20), tile(1, 1)] {
+        for x in (0..40).step_by(3) {
+            for y in (0..40).step_by(3) {
+                if x == 20 {
+                    continue;
+                }
+                let cost = dijkstra(&grid, &tile(x, y), &target, &Costs::NONE, &GameState::default(), &Limits::default()).path.unwrap().cost;
+                assert!(grid.landmarks.estimate(tile(x, y).index(), target.index()) <= cost);
+            }
+        }
+    }
+}
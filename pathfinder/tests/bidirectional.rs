@@ -0,0 +1,87 @@
+use model::{Coordinate, Edge, NavGrid};
+use model::definitions::{EdgeDefinition, GameState, RequirementDefinition, Skill};
+use pathfinder::costs::Costs;
+use pathfinder::limits::Limits;
+use pathfinder::path::Path;
+use pathfinder::{bidirectional_dijkstra, dijkstra};
+
+use crate::common::{grid, tile};
+
+mod common;
+
+/// Wall at x = 20 across the whole grid, crossed only by a one-way door from (19, 20) to (21, 20) taking 2
+fn one_way(requirements: Vec<RequirementDefinition>) -> NavGrid {
+    let mut grid = grid(40, 40, |x, _| x != 20);
+    let definition = EdgeDefinition::Door { id: 1, position: tile(20, 20), action: "Open".parse().unwrap() };
+    grid.edges.insert(tile(19, 20).index(), Edge { destination: tile(21, 20), cost: 2, definition, requirements });
+    grid.vertices[tile(19, 20).index()].set_extra_edges(true);
+    grid
+}
+
+/// Both searches find paths of the same cost, or neither finds one. Returns the bidirectional path.
+fn compare(nav_grid: &NavGrid, start: Coordinate, end: Coordinate, game_state: &GameState) -> Option<Path> {
+    let expected = dijkstra(nav_grid, &start, &end, &Costs::NONE, game_state, &Limits::default()).path;
+    let actual = bidirectional_dijkstra(nav_grid, &start, &end, game_state, &Limits::default()).path;
+    assert_eq!(actual.as_ref().map(|path| path.cost), expected.as_ref().map(|path| path.cost), "{:?} to {:?}", start, end);
+    let path = actual?;
+    assert_eq!(path.end, end);
+    // Cumulative costs step by each edge's cost, walks only move to adjacent tiles
+    let mut previous = (start, 0);
+    for step in &path.steps {
+        assert_eq!(step.cost, previous.1 + step.edge.cost);
+        if step.is_walk() {
+            assert_eq!(previous.0.chebyshev_distance(&step.position), 1);
+        }
+        previous = (step.position, step.cost);
+    }
+    assert_eq!(previous.1, path.cost);
+    Some(path)
+}
+
+#[test]
+fn costs_match_dijkstra() {
+    let grid = grid(40, 40, |x, y| !((x == 12 && y <= 30) || (x == 25 && y >= 10)));
+    let pairs = [((2, 2), (37, 37)), ((37, 2), (2, 37)), ((5, 35), (30, 5)), ((13, 0), (24, 39)), ((11, 30), (13, 30))];
+    for ((x1, y1), (x2, y2)) in pairs {
+        compare(&grid, tile(x1, y1), tile(x2, y2), &GameState::default()).unwrap();
+    }
+}
+
+#[test]
+fn start_is_end() {
+    let grid = grid(10, 10, |_, _| true);
+    let path = compare(&grid, tile(4, 4), tile(4, 4), &GameState::default()).unwrap();
+    assert_eq!(path.cost, 0);
+    assert!(path.steps.is_empty());
+}
+
+#[test]
+fn one_way_edges_are_only_taken_forward() {
+    let grid = one_way(Vec::new());
+    let path = compare(&grid, tile(2, 20), tile(37, 20), &GameState::default()).unwrap();
+    assert_eq!(path.cost, 35);
+    assert!(path.steps.iter().any(|step| matches!(step.edge.definition, EdgeDefinition::Door { .. })));
+    assert!(compare(&grid, tile(37, 20), tile(2, 20), &GameState::default()).is_none());
+}
+
+#[test]
+fn requirements_apply_to_reverse_edges() {
+    let grid = one_way(vec![RequirementDefinition::Skill { skill: Skill::Agility, level: 30 }]);
+    assert!(compare(&grid, tile(2, 20), tile(37, 20), &GameState::default()).is_none());
+    let agile = GameState { skills: [(Skill::Agility, 30)].into(), ..GameState::default() };
+    assert_eq!(compare(&grid, tile(2, 20), tile(37, 20), &agile).unwrap().cost, 35);
+}
+
+#[test]
+fn teleports_seed_only_the_forward_side() {
+    // Two halves without a way between them but the teleport
+    let halves = || grid(40, 40, |x, _| x != 20);
+    assert!(compare(&halves(), tile(2, 2), tile(35, 35), &GameState::default()).is_none());
+    let mut grid = halves();
+    grid.teleports.push(Edge { destination: tile(30, 30), cost: 5, definition: EdgeDefinition::SpellTeleport { spell: "Test".to_string() }, requirements: Vec::new() });
+    let path = compare(&grid, tile(2, 2), tile(35, 35), &GameState::default()).unwrap();
+    assert_eq!(path.cost, 10);
+    assert!(matches!(path.steps[0].edge.definition, EdgeDefinition::SpellTeleport { .. }));
+    // The teleport leads away from the start's half, so there is still no way back
+    assert!(compare(&grid, tile(35, 35), tile(2, 2), &GameState::default()).is_none());
+}
@@ -0,0 +1,75 @@
+use model::{Coordinate, NavGrid};
+use model::definitions::GameState;
+use pathfinder::client::ClientRoutes;
+use pathfinder::compress::Waypoints;
+use pathfinder::costs::Costs;
+use pathfinder::dijkstra;
+use pathfinder::limits::Limits;
+
+use crate::common::{ORIGIN, tile};
+
+mod common;
+
+/// Open field of 40 by 40 tiles except where `open` says otherwise
+fn grid(open: impl Fn(i32, i32) -> bool) -> NavGrid {
+    common::grid(40, 40, open)
+}
+
+fn tiles(route: &[Coordinate]) -> Vec<(i32, i32)> {
+    route.iter().map(|c| (c.x as i32 - ORIGIN.x as i32, c.y as i32 - ORIGIN.y as i32)).collect()
+}
+
+#[test]
+fn open_field_prefers_straight_steps_first() {
+    let grid = grid(|_, _| true);
+    let routes = ClientRoutes::new(&grid, &tile(10, 10));
+    assert_eq!(tiles(&routes.route(&tile(13, 11)).unwrap()), vec![(11, 10), (12, 10), (13, 11)]);
+    assert_eq!(tiles(&routes.route(&tile(7, 7)).unwrap()), vec![(9, 9), (8, 8), (7, 7)]);
+    assert_eq!(routes.route(&tile(10, 10)).unwrap(), vec![]);
+}
+
+#[test]
+fn wall_forces_detour() {
+    // Wall at x = 12 from y = 3 up to y = 15, the route from (10, 10) to (14, 10) goes around its north end
+    let grid = grid(|x, y| !(x == 12 && (3..=15).contains(&y)));
+    let routes = ClientRoutes::new(&grid, &tile(10, 10));
+    let route = routes.route(&tile(14, 10)).unwrap();
+    assert_eq!(route.len(), 14);
+    assert_eq!(routes.distance(&tile(14, 10)), Some(14));
+    assert_eq!(tiles(&route)[6..8], [(12, 16), (13, 16)]);
+    let mut previous = tile(10, 10);
+    for step in &route {
+        assert_eq!(routes.previous(step), Some(previous));
+        previous = *step;
+    }
+}
+
+#[test]
+fn unreachable_destination_walks_to_closest_tile() {
+    let grid = grid(|x, y| (x, y) != (15, 10));
+    let routes = ClientRoutes::new(&grid, &tile(10, 10));
+    assert_eq!(routes.distance(&tile(15, 10)), None);
+    assert_eq!(routes.route(&tile(15, 10)).unwrap().last(), Some(&tile(14, 10)));
+    assert_eq!(routes.route(&Coordinate { plane: 1, ..tile(15, 10) }), None);
+}
+
+#[test]
+fn client_waypoints_reproduce_path() {
+    // Scattered blocked tiles, so paths have many equally short alternatives
+    let grid = grid(|x, y| (x * 7 + y * 13) % 11 != 0 || (x + y) % 3 == 0);
+    let (start, end) = (tile(1, 2), tile(37, 35));
+    let path = dijkstra(&grid, &start, &end, &Costs::NONE, &GameState::default(), &Limits::default()).path.unwrap();
+    for max_distance in [1, 4, 15] {
+        let compressed = path.compress(&grid, max_distance, Waypoints::Client);
+        assert_eq!(compressed.cost, path.cost);
+        assert!(compressed.steps.len() < path.steps.len() || max_distance == 1);
+        let mut walked = Vec::new();
+        let mut current = start;
+        for step in &compressed.steps {
+            assert!(current.chebyshev_distance(&step.position) <= max_distance);
+            walked.extend(ClientRoutes::new(&grid, &current).route(&step.position).unwrap());
+            current = step.position;
+        }
+        assert_eq!(walked, path.steps.iter().map(|step| step.position).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,30 @@
+use model::{Coordinate, NavGrid};
+use model::constants::DIRECTIONS;
+
+pub const ORIGIN: Coordinate = Coordinate { x: 3200, y: 3200, plane: 0 };
+
+pub fn tile(x: i32, y: i32) -> Coordinate {
+    ORIGIN.derive(x as i16, y as i16, 0)
+}
+
+/// Grid of `width` by `height` tiles from ORIGIN with the tiles `open` returns true for, moves are allowed between open
+/// tiles unless they cut a blocked corner
+pub fn grid(width: i32, height: i32, open: impl Fn(i32, i32) -> bool) -> NavGrid {
+    let open = |x: i32, y: i32| (0..width).contains(&x) && (0..height).contains(&y) && open(x, y);
+    let mut grid = NavGrid::new();
+    for x in 0..width {
+        for y in 0..height {
+            if !open(x, y) {
+                continue;
+            }
+            let mut flags = 0;
+            for (flag, dx, dy) in &DIRECTIONS {
+                if open(x + dx, y + dy) && open(x + dx, y) && open(x, y + dy) {
+                    flags |= flag;
+                }
+            }
+            grid.vertices[tile(x, y).index()].flags = flags;
+        }
+    }
+    grid
+}
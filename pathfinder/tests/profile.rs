@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use model::{Edge, NavGrid};
+use model::definitions::{EdgeDefinition, EdgeKind, GameState, ItemContainer, RequirementDefinition};
+use pathfinder::compress::Waypoints;
+use pathfinder::costs::Costs;
+use pathfinder::dijkstra;
+use pathfinder::limits::Limits;
+use pathfinder::path::Path;
+use pathfinder::profile::CostProfile;
+use regex::Regex;
+
+use crate::common::{grid, tile};
+
+mod common;
+
+fn item(name: &str) -> RequirementDefinition {
+    RequirementDefinition::Item { item: Regex::new(name).unwrap(), quantity: 1, container: ItemContainer::Carried }
+}
+
+/// Corridor of 30 steps from the start to the end, with a teleport of cost 5 requiring the requirement next to the end
+fn teleport_grid(requirement: RequirementDefinition) -> NavGrid {
+    let mut grid = grid(31, 1, |_, _| true);
+    grid.teleports.push(Edge { destination: tile(29, 0), cost: 5, definition: EdgeDefinition::SpellTeleport { spell: "Test".to_string() }, requirements: vec![requirement] });
+    grid
+}
+
+fn search(nav_grid: &NavGrid, profile: &CostProfile) -> Option<Path> {
+    let game_state = GameState { inventory: HashMap::from([("Law rune".to_string(), 1)]), ..GameState::default() };
+    let costs = Costs { zones: &[], profile: Some(profile) };
+    dijkstra(nav_grid, &tile(0, 0), &tile(30, 0), &costs, &game_state, &Limits::default()).path
+}
+
+fn cost(nav_grid: &NavGrid, profile: &CostProfile) -> Option<u32> {
+    search(nav_grid, profile).map(|path| path.cost)
+}
+
+#[test]
+fn empty_profile_keeps_costs() {
+    assert_eq!(cost(&teleport_grid(item("Law rune")), &CostProfile::default()), Some(6));
+}
+
+#[test]
+fn consumed_items_are_penalized() {
+    let grid = teleport_grid(item("Law rune"));
+    let ironman = CostProfile { items: HashMap::from([("Law rune".to_string(), 30)]), ..CostProfile::default() };
+    assert_eq!(cost(&grid, &ironman), Some(30));
+    let frugal = CostProfile { items: HashMap::from([("Law rune".to_string(), 10)]), ..CostProfile::default() };
+    assert_eq!(cost(&grid, &frugal), Some(16));
+}
+
+#[test]
+fn alternatives_cost_the_cheapest_option() {
+    let grid = teleport_grid(RequirementDefinition::AnyOf(vec![item("Law rune"), item("Teleport tablet")]));
+    let profile = CostProfile { items: HashMap::from([("Law rune".to_string(), 30)]), ..CostProfile::default() };
+    assert_eq!(cost(&grid, &profile), Some(6));
+    let grid = teleport_grid(RequirementDefinition::AllOf(vec![item("Law rune"), item("Law rune")]));
+    let profile = CostProfile { items: HashMap::from([("Law rune".to_string(), 10)]), ..CostProfile::default() };
+    assert_eq!(cost(&grid, &profile), Some(26));
+}
+
+#[test]
+fn kinds_are_rescaled() {
+    let grid = teleport_grid(item("Law rune"));
+    let speedrun = CostProfile { kinds: HashMap::from([(EdgeKind::Step, 2.0)]), ..CostProfile::default() };
+    assert_eq!(cost(&grid, &speedrun), Some(7));
+    let no_teleports = CostProfile { kinds: HashMap::from([(EdgeKind::SpellTeleport, 10.0)]), ..CostProfile::default() };
+    assert_eq!(cost(&grid, &no_teleports), Some(30));
+}
+
+#[test]
+fn edges_report_rescaled_costs() {
+    let grid = teleport_grid(item("Law rune"));
+    let speedrun = CostProfile { kinds: HashMap::from([(EdgeKind::Step, 2.0)]), ..CostProfile::default() };
+    let path = search(&grid, &speedrun).unwrap();
+    assert_eq!(path.steps.iter().map(|step| step.edge.cost).collect::<Vec<_>>(), vec![5, 2]);
+    let ironman = CostProfile { items: HashMap::from([("Law rune".to_string(), 10)]), ..CostProfile::default() };
+    let path = search(&grid, &ironman).unwrap();
+    assert_eq!(path.steps.iter().map(|step| step.edge.cost).collect::<Vec<_>>(), vec![15, 1]);
+    assert_eq!(path.steps.iter().map(|step| step.edge.cost).sum::<u32>(), path.cost);
+}
+
+#[test]
+fn small_multipliers_keep_moves_from_becoming_free() {
+    let grid = teleport_grid(item("Law rune"));
+    let cheap = CostProfile { kinds: HashMap::from([(EdgeKind::Step, 0.25), (EdgeKind::SpellTeleport, 0.05)]), ..CostProfile::default() };
+    assert_eq!(cost(&grid, &cheap), Some(2));
+    let free = CostProfile { kinds: HashMap::from([(EdgeKind::Step, 0.0)]), ..CostProfile::default() };
+    assert_eq!(cost(&grid, &free), Some(0));
+}
+
+#[test]
+fn compressed_waypoints_cost_the_rescaled_steps() {
+    let grid = teleport_grid(item("Law rune"));
+    let profile = CostProfile { kinds: HashMap::from([(EdgeKind::Step, 2.0), (EdgeKind::SpellTeleport, 100.0)]), ..CostProfile::default() };
+    let path = search(&grid, &profile).unwrap().compress(&grid, 15, Waypoints::Line);
+    assert_eq!(path.steps.iter().map(|step| step.edge.cost).collect::<Vec<_>>(), vec![30, 30]);
+    assert_eq!(path.cost, 60);
+}
+
+#[test]
+fn misspelled_fields_are_rejected() {
+    assert!(serde_json::from_str::<CostProfile>(r#"{ "kinds": { "SpellTeleport": 0.5 } }"#).is_ok());
+    assert!(serde_json::from_str::<CostProfile>(r#"{ "kind": { "SpellTeleport": 0.5 } }"#).is_err());
+    assert!(serde_json::from_str::<CostProfile>(r#"{ "kinds": { "SpelTeleport": 0.5 } }"#).is_err());
+}
+
+#[test]
+fn shipped_profiles_parse() {
+    let profiles: HashMap<String, CostProfile> = serde_json::from_str(include_str!("../../webservice/Profiles.json")).unwrap();
+    assert!(profiles.values().all(CostProfile::validate));
+}
@@ -0,0 +1,84 @@
+use model::{Edge, NavGrid};
+use model::area::Area;
+use model::definitions::{EdgeDefinition, GameState};
+use pathfinder::compress::Waypoints;
+use pathfinder::costs::Costs;
+use pathfinder::dijkstra;
+use pathfinder::limits::Limits;
+use pathfinder::path::Path;
+use pathfinder::route::route;
+use pathfinder::zones::{Zone, ZoneCost};
+
+use crate::common::{grid, tile};
+
+mod common;
+
+/// Road of 10 steps from (0, 0) to (10, 0), and a detour of 14 steps two tiles north of it joined at both ends
+fn road() -> NavGrid {
+    grid(11, 3, |x, y| y != 1 || x == 0 || x == 10)
+}
+
+fn zone(min: (i32, i32), max: (i32, i32), cost: ZoneCost) -> Zone {
+    Zone { area: Area::Rectangle { min: tile(min.0, min.1), max: tile(max.0, max.1) }, cost }
+}
+
+fn search(nav_grid: &NavGrid, zones: &[Zone]) -> Option<Path> {
+    dijkstra(nav_grid, &tile(0, 0), &tile(10, 0), &Costs { zones, profile: None }, &GameState::default(), &Limits::default()).path
+}
+
+#[test]
+fn blocked_zone_is_walked_around() {
+    let zones = [zone((4, 0), (6, 0), ZoneCost::Blocked)];
+    let path = search(&road(), &zones).unwrap();
+    assert!(path.steps.iter().all(|step| !zones[0].area.contains(&step.position)));
+    assert_eq!(path.cost, 14);
+    assert_eq!(path.cost, path.steps.len() as u32);
+}
+
+#[test]
+fn blocked_end_has_no_path() {
+    assert!(search(&road(), &[zone((10, 0), (10, 0), ZoneCost::Blocked)]).is_none());
+}
+
+#[test]
+fn multiplier_scales_moves_into_zone() {
+    let grid = road();
+    assert_eq!(search(&grid, &[]).unwrap().cost, 10);
+    // Cheaper to cross than to take the detour
+    let path = search(&grid, &[zone((5, 0), (5, 0), ZoneCost::Multiplier(3.0))]).unwrap();
+    assert_eq!(path.cost, 12);
+    assert!(path.steps.iter().any(|step| step.position == tile(5, 0)));
+    let avoided = [zone((4, 0), (6, 0), ZoneCost::Multiplier(3.0))];
+    let path = search(&grid, &avoided).unwrap();
+    assert_eq!(path.cost, 14);
+    assert!(path.steps.iter().all(|step| !avoided[0].area.contains(&step.position)));
+    // Overlapping multipliers multiply
+    let path = search(&grid, &[zone((5, 0), (5, 0), ZoneCost::Multiplier(1.5)), zone((5, 0), (5, 0), ZoneCost::Multiplier(2.0))]).unwrap();
+    assert_eq!(path.cost, 12);
+    // Moves cost at least 1 unless the multiplier is 0
+    assert_eq!(search(&grid, &[zone((5, 0), (5, 0), ZoneCost::Multiplier(0.1))]).unwrap().cost, 10);
+    assert_eq!(search(&grid, &[zone((5, 0), (5, 0), ZoneCost::Multiplier(0.0))]).unwrap().cost, 9);
+}
+
+#[test]
+fn zones_apply_to_teleports() {
+    let mut grid = road();
+    grid.teleports.push(Edge { destination: tile(9, 0), cost: 3, definition: EdgeDefinition::SpellTeleport { spell: "Test".to_string() }, requirements: Vec::new() });
+    assert_eq!(search(&grid, &[]).unwrap().cost, 4);
+    assert_eq!(search(&grid, &[zone((9, 0), (9, 0), ZoneCost::Multiplier(2.0))]).unwrap().cost, 7);
+    assert_eq!(search(&grid, &[zone((9, 0), (9, 0), ZoneCost::Blocked)]).unwrap().cost, 14);
+}
+
+#[test]
+fn compressed_waypoints_cost_the_weighted_moves() {
+    let grid = road();
+    let zones = [zone((1, 0), (1, 0), ZoneCost::Multiplier(3.0)), zone((6, 0), (6, 0), ZoneCost::Multiplier(3.0))];
+    let path = search(&grid, &zones).unwrap().compress(&grid, 15, Waypoints::Line);
+    assert_eq!(path.steps.len(), 1);
+    assert_eq!(path.steps[0].edge.cost, 14);
+    let costs = Costs { zones: &zones, profile: None };
+    let route = route(&grid, &[tile(0, 0), tile(5, 0), tile(10, 0)], &costs, &GameState::default(), &Limits::default()).route.unwrap();
+    let compressed = route.compress(&grid, 15, Waypoints::Line);
+    assert_eq!(compressed.path.steps.iter().map(|step| step.edge.cost).collect::<Vec<_>>(), vec![7, 7]);
+    assert_eq!(compressed.path.cost, 14);
+}
@@ -1,37 +1,239 @@
 #[macro_use]
 extern crate rocket;
 
-use std::collections::HashSet;
-use std::fs::File;
-use std::io::{BufReader, Read};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
 use std::path::{Path, PathBuf};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use clap::Parser;
 use expect_exit::ExpectedWithError;
-use flate2::read::GzDecoder;
 use rocket::{Build, Rocket, State};
-use rocket::response::status::BadRequest;
-use rocket::serde::json::Json;
+use rocket::http::Status;
+use rocket::response::status::{BadRequest, Custom};
+use rocket::serde::json::{Json, serde_json};
 use rocket_prometheus::PrometheusMetrics;
 use serde::{Deserialize, Serialize};
 
 use model::{Coordinate, NavGrid};
-use model::definitions::{EdgeDefinition, GameState, RequirementDefinition};
+use model::area::Area;
+use model::definitions::{GameState, QUEST_POINTS_VARP, QuestVar, RequirementDefinition, Skill};
+use model::format::FormatError;
+use pathfinder::compress::Waypoints;
+use pathfinder::costs::Costs;
+use pathfinder::limits::{Limit, Limits};
+use pathfinder::planner::Plan;
+use pathfinder::profile::CostProfile;
+use pathfinder::zones::Zone;
 
 #[derive(Parser)]
 struct Options {
     /// Path to NavGrid file
     #[clap(short, long)]
     navgrid: PathBuf,
+    /// Path to a JSON file of named cost profiles
+    #[clap(long)]
+    profiles: Option<PathBuf>,
+    #[clap(flatten)]
+    limits: RequestLimits,
+}
+
+/// Cost profiles requests may select by name
+#[derive(Default)]
+struct Profiles(HashMap<String, CostProfile>);
+
+/// A cost profile by its name, or given inline
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ProfileChoice {
+    Named(String),
+    Inline(CostProfile),
+}
+
+impl ProfileChoice {
+    fn resolve<'a>(&'a self, profiles: &'a Profiles) -> Option<&'a CostProfile> {
+        match self {
+            ProfileChoice::Named(name) => profiles.0.get(name),
+            ProfileChoice::Inline(profile) => Some(profile),
+        }
+    }
+}
+
+/// Limits of a single search, requests may lower the defaults the server is started with
+#[derive(Clone, Copy, Default, Deserialize, clap::Args)]
+struct RequestLimits {
+    /// Highest path cost to search up to
+    #[clap(long)]
+    max_cost: Option<u32>,
+    /// Most vertices to visit per search
+    #[clap(long)]
+    max_visited: Option<usize>,
+    /// Time limit per search in milliseconds
+    #[clap(long)]
+    timeout: Option<u64>,
+}
+
+impl RequestLimits {
+    fn lower(self, other: RequestLimits) -> RequestLimits {
+        fn min<T: Ord>(a: Option<T>, b: Option<T>) -> Option<T> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            }
+        }
+        RequestLimits {
+            max_cost: min(self.max_cost, other.max_cost),
+            max_visited: min(self.max_visited, other.max_visited),
+            timeout: min(self.timeout, other.timeout),
+        }
+    }
+
+    fn to_limits(self, begin: Instant) -> Limits {
+        Limits {
+            max_cost: self.max_cost,
+            max_visited: self.max_visited,
+            deadline: self.timeout.map(|timeout| begin + Duration::from_millis(timeout)),
+            cancellation: None,
+        }
+    }
 }
 
 #[derive(Deserialize)]
 struct Request {
+    start: Coordinate,
+    end: End,
+    #[serde(default)]
+    game_state: GameState,
+    #[serde(default)]
+    algorithm: Algorithm,
+    /// Areas blocked or more expensive for this request only
+    #[serde(default)]
+    zones: Vec<Zone>,
+    #[serde(default)]
+    profile: Option<ProfileChoice>,
+    #[serde(default)]
+    limits: RequestLimits,
+    /// Merges walks into waypoints at most this many tiles apart
+    #[serde(default)]
+    compress: Option<u32>,
+    /// How compressed waypoints are chosen
+    #[serde(default)]
+    compression: Waypoints,
+}
+
+#[derive(Deserialize)]
+struct RouteRequest {
+    waypoints: Vec<Coordinate>,
+    #[serde(default)]
+    game_state: GameState,
+    #[serde(default)]
+    zones: Vec<Zone>,
+    #[serde(default)]
+    profile: Option<ProfileChoice>,
+    #[serde(default)]
+    limits: RequestLimits,
+    #[serde(default)]
+    compress: Option<u32>,
+    #[serde(default)]
+    compression: Waypoints,
+}
+
+/// Most stops of a plan, the cost matrix between them takes a search per stop
+const MAX_PLAN_STOPS: usize = 25;
+
+#[derive(Deserialize)]
+struct PlanRequest {
+    start: Coordinate,
+    stops: Vec<Coordinate>,
+    #[serde(default)]
+    end: Option<Coordinate>,
+    #[serde(default)]
+    game_state: GameState,
+    #[serde(default)]
+    limits: RequestLimits,
+    #[serde(default)]
+    compress: Option<u32>,
+    #[serde(default)]
+    compression: Waypoints,
+}
+
+#[derive(Deserialize)]
+struct AlternativesRequest {
     start: Coordinate,
     end: Coordinate,
     #[serde(default)]
     game_state: GameState,
+    /// Most paths to return
+    #[serde(default = "default_alternatives")]
+    count: usize,
+    /// Highest fraction of tiles a path may share with each other path
+    #[serde(default = "default_max_overlap")]
+    max_overlap: f64,
+    #[serde(default)]
+    limits: RequestLimits,
+    #[serde(default)]
+    compress: Option<u32>,
+    #[serde(default)]
+    compression: Waypoints,
+}
+
+fn default_alternatives() -> usize {
+    3
+}
+
+fn default_max_overlap() -> f64 {
+    0.5
+}
+
+/// Most alternative paths a single request may ask for
+const MAX_ALTERNATIVES: usize = 10;
+
+/// Most origins and targets of a cost matrix, each origin takes a search of its own
+const MAX_MATRIX_ORIGINS: usize = 50;
+const MAX_MATRIX_TARGETS: usize = 500;
+
+#[derive(Deserialize)]
+struct MatrixRequest {
+    origins: Vec<Coordinate>,
+    targets: Vec<Coordinate>,
+    #[serde(default)]
+    game_state: GameState,
+    #[serde(default)]
+    limits: RequestLimits,
+}
+
+/// Cost from each origin (rows) to each target (columns), along with the limit stopping an origin's search
+#[derive(Serialize)]
+struct MatrixResponse {
+    costs: Vec<Vec<Option<u32>>>,
+    stopped: Vec<Option<Limit>>,
+}
+
+/// A single end, a list of ends or an area, the path leads to the cheapest to reach end or tile of the area
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum End {
+    Single(Coordinate),
+    Nearest(Vec<Coordinate>),
+    Area(Area),
+}
+
+impl Display for End {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            End::Single(end) => write!(f, "{}", end),
+            End::Nearest(ends) => write!(f, "nearest of {}", ends.len()),
+            End::Area(_) => write!(f, "area"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+enum Algorithm {
+    #[default]
+    Dijkstra,
+    AStar,
+    Bidirectional,
 }
 
 #[derive(Clone, Default, Serialize)]
@@ -39,20 +241,177 @@ struct DataSelection {
     varps: HashSet<u32>,
     varbits: HashSet<u32>,
     items: HashSet<String>,
-    skills: HashSet<String>,
+    skills: HashSet<Skill>,
+    bank: bool,
+}
+
+#[derive(Responder)]
+enum PathError {
+    Invalid(BadRequest<&'static str>),
+    /// The search was stopped by a limit before finding a path, the body names the limit
+    Limited(Custom<Json<Limit>>),
+    /// The request asks for more searches than a single request may
+    TooLarge(Custom<&'static str>),
+}
+
+/// Costs of the request's zones and profile, selected by name from the profiles or given inline
+fn request_costs<'a>(zones: &'a [Zone], profile: &'a Option<ProfileChoice>, profiles: &'a Profiles) -> Result<Costs<'a>, PathError> {
+    if !zones.iter().all(Zone::validate) {
+        return Err(PathError::Invalid(BadRequest("Invalid zone area or multiplier")));
+    }
+    let profile = match profile {
+        Some(choice) => Some(choice.resolve(profiles).ok_or(PathError::Invalid(BadRequest("Unknown cost profile")))?),
+        None => None,
+    };
+    if matches!(profile, Some(profile) if !profile.validate()) {
+        return Err(PathError::Invalid(BadRequest("Invalid cost profile multiplier")));
+    }
+    Ok(Costs { zones, profile })
 }
 
 #[post("/", data = "<request>")]
-fn handle_path_request(request: Json<Request>, nav_grid: &State<NavGrid>) -> Result<Json<Option<Vec<EdgeDefinition>>>, BadRequest<&str>> {
-    if !request.start.validate() || !request.end.validate() {
+fn handle_path_request(request: Json<Request>, nav_grid: &State<NavGrid>, limits: &State<RequestLimits>, profiles: &State<Profiles>) -> Result<Json<Option<pathfinder::path::Path>>, PathError> {
+    let valid = request.start.validate() && match &request.end {
+        End::Single(end) => end.validate(),
+        End::Nearest(ends) => ends.iter().all(Coordinate::validate),
+        End::Area(area) => area.validate(),
+    };
+    if !valid {
         println!("[Path] {} -> {} invalid coordinates", request.start, request.end);
-        Err(BadRequest("Coordinate out of bounds"))
+        Err(PathError::Invalid(BadRequest("Coordinate out of bounds")))
+    } else if matches!(&request.end, End::Nearest(ends) if ends.is_empty()) {
+        Err(PathError::Invalid(BadRequest("No end coordinates")))
+    } else if request.compress == Some(0) {
+        Err(PathError::Invalid(BadRequest("Waypoint distance must be positive")))
+    } else {
+        let costs = request_costs(&request.zones, &request.profile, profiles)?;
+        if !costs.is_none() && !matches!(request.algorithm, Algorithm::Dijkstra) {
+            return Err(PathError::Invalid(BadRequest("Zones and cost profiles are only supported by Dijkstra")));
+        }
+        let begin = Instant::now();
+        let limits = limits.lower(request.limits).to_limits(begin);
+        let result = match (&request.end, request.algorithm) {
+            (End::Single(end), Algorithm::Dijkstra) => pathfinder::dijkstra(nav_grid, &request.start, end, &costs, &request.game_state, &limits),
+            (End::Single(end), Algorithm::AStar) => pathfinder::astar(nav_grid, &request.start, end, &request.game_state, &limits),
+            (End::Single(end), Algorithm::Bidirectional) => pathfinder::bidirectional_dijkstra(nav_grid, &request.start, end, &request.game_state, &limits),
+            (End::Nearest(ends), Algorithm::Dijkstra) => pathfinder::dijkstra_nearest(nav_grid, &request.start, ends, &costs, &request.game_state, &limits),
+            (End::Area(area), Algorithm::Dijkstra) => pathfinder::dijkstra_area(nav_grid, &request.start, area, &costs, &request.game_state, &limits),
+            _ => return Err(PathError::Invalid(BadRequest("End lists and areas are only supported by Dijkstra"))),
+        };
+        let duration = Instant::now() - begin;
+        println!("[Path] {} -> {} {:?} in {:.2}ms, {}Kb, {} visited{}", request.start, request.end, request.algorithm, duration.as_secs_f64() * 1000f64,
+                 result.mem_usage / 1024, result.visited, result.stopped.map_or(String::new(), |limit| format!(", stopped by {:?}", limit)));
+        match result.stopped {
+            Some(limit) => Err(PathError::Limited(Custom(Status::UnprocessableEntity, Json(limit)))),
+            None => Ok(Json(result.path.map(|path| match request.compress {
+                Some(max_distance) => path.compress(nav_grid, max_distance, request.compression),
+                None => path,
+            }))),
+        }
+    }
+}
+
+#[post("/", data = "<request>")]
+fn handle_route_request(request: Json<RouteRequest>, nav_grid: &State<NavGrid>, limits: &State<RequestLimits>, profiles: &State<Profiles>) -> Result<Json<Option<pathfinder::route::Route>>, PathError> {
+    if !request.waypoints.iter().all(Coordinate::validate) {
+        println!("[Route] {} waypoints invalid coordinates", request.waypoints.len());
+        Err(PathError::Invalid(BadRequest("Coordinate out of bounds")))
+    } else if request.waypoints.len() < 2 {
+        Err(PathError::Invalid(BadRequest("Less than two waypoints")))
+    } else if request.compress == Some(0) {
+        Err(PathError::Invalid(BadRequest("Waypoint distance must be positive")))
+    } else {
+        let costs = request_costs(&request.zones, &request.profile, profiles)?;
+        let begin = Instant::now();
+        let limits = limits.lower(request.limits).to_limits(begin);
+        let result = pathfinder::route::route(nav_grid, &request.waypoints, &costs, &request.game_state, &limits);
+        let duration = Instant::now() - begin;
+        println!("[Route] {} -> {} via {} waypoints in {:.2}ms, {}Kb, {} visited{}", request.waypoints[0], request.waypoints[request.waypoints.len() - 1],
+                 request.waypoints.len() - 2, duration.as_secs_f64() * 1000f64, result.mem_usage / 1024, result.visited,
+                 result.stopped.map_or(String::new(), |limit| format!(", stopped by {:?}", limit)));
+        match result.stopped {
+            Some(limit) => Err(PathError::Limited(Custom(Status::UnprocessableEntity, Json(limit)))),
+            None => Ok(Json(result.route.map(|route| match request.compress {
+                Some(max_distance) => route.compress(nav_grid, max_distance, request.compression),
+                None => route,
+            }))),
+        }
+    }
+}
+
+#[post("/", data = "<request>")]
+fn handle_plan_request(request: Json<PlanRequest>, nav_grid: &State<NavGrid>, limits: &State<RequestLimits>) -> Result<Json<Option<Plan>>, PathError> {
+    if !(request.start.validate() && request.stops.iter().chain(&request.end).all(Coordinate::validate)) {
+        println!("[Plan] {} stops invalid coordinates", request.stops.len());
+        Err(PathError::Invalid(BadRequest("Coordinate out of bounds")))
+    } else if request.stops.len() > MAX_PLAN_STOPS {
+        Err(PathError::TooLarge(Custom(Status::UnprocessableEntity, "More than 25 stops")))
+    } else if request.compress == Some(0) {
+        Err(PathError::Invalid(BadRequest("Waypoint distance must be positive")))
     } else {
         let begin = Instant::now();
-        let (visited, mem_usage, path) = pathfinder::dijkstra(&nav_grid, &request.start, &request.end, &request.game_state);
+        let limits = limits.lower(request.limits).to_limits(begin);
+        let result = pathfinder::planner::plan(nav_grid, &request.start, &request.stops, request.end.as_ref(), &request.game_state, &limits);
         let duration = Instant::now() - begin;
-        println!("[Path] {} -> {} in {:.2}ms, {}Kb, {} visited", request.start, request.end, duration.as_secs_f64() * 1000f64, mem_usage / 1024, visited);
-        Ok(Json(path))
+        println!("[Plan] {} stops from {} in {:.2}ms, {} visited{}", request.stops.len(), request.start, duration.as_secs_f64() * 1000f64, result.visited,
+                 result.stopped.map_or(String::new(), |limit| format!(", stopped by {:?}", limit)));
+        match (result.plan, result.stopped) {
+            (None, Some(limit)) => Err(PathError::Limited(Custom(Status::UnprocessableEntity, Json(limit)))),
+            (plan, _) => Ok(Json(plan.map(|plan| match request.compress {
+                Some(max_distance) => Plan { route: plan.route.compress(nav_grid, max_distance, request.compression), ..plan },
+                None => plan,
+            }))),
+        }
+    }
+}
+
+#[post("/", data = "<request>")]
+fn handle_alternatives_request(request: Json<AlternativesRequest>, nav_grid: &State<NavGrid>, limits: &State<RequestLimits>) -> Result<Json<Vec<pathfinder::path::Path>>, PathError> {
+    if !(request.start.validate() && request.end.validate()) {
+        println!("[Alternatives] {} -> {} invalid coordinates", request.start, request.end);
+        Err(PathError::Invalid(BadRequest("Coordinate out of bounds")))
+    } else if !(1..=MAX_ALTERNATIVES).contains(&request.count) {
+        Err(PathError::Invalid(BadRequest("Path count must be between 1 and 10")))
+    } else if !(0.0..=1.0).contains(&request.max_overlap) {
+        Err(PathError::Invalid(BadRequest("Overlap must be between 0 and 1")))
+    } else if request.compress == Some(0) {
+        Err(PathError::Invalid(BadRequest("Waypoint distance must be positive")))
+    } else {
+        let begin = Instant::now();
+        let limits = limits.lower(request.limits).to_limits(begin);
+        let result = pathfinder::alternatives::alternatives(nav_grid, &request.start, &request.end, request.count, request.max_overlap, &request.game_state, &limits);
+        let duration = Instant::now() - begin;
+        println!("[Alternatives] {} -> {} {} of {} in {:.2}ms, {}Kb, {} visited{}", request.start, request.end, result.paths.len(), request.count,
+                 duration.as_secs_f64() * 1000f64, result.mem_usage / 1024, result.visited,
+                 result.stopped.map_or(String::new(), |limit| format!(", stopped by {:?}", limit)));
+        match (result.paths.is_empty(), result.stopped) {
+            (true, Some(limit)) => Err(PathError::Limited(Custom(Status::UnprocessableEntity, Json(limit)))),
+            _ => Ok(Json(result.paths.into_iter().map(|path| match request.compress {
+                Some(max_distance) => path.compress(nav_grid, max_distance, request.compression),
+                None => path,
+            }).collect())),
+        }
+    }
+}
+
+#[post("/", data = "<request>")]
+fn handle_matrix_request(request: Json<MatrixRequest>, nav_grid: &State<NavGrid>, limits: &State<RequestLimits>) -> Result<Json<MatrixResponse>, PathError> {
+    if !request.origins.iter().chain(&request.targets).all(Coordinate::validate) {
+        println!("[Matrix] {}x{} invalid coordinates", request.origins.len(), request.targets.len());
+        Err(PathError::Invalid(BadRequest("Coordinate out of bounds")))
+    } else if request.origins.len() > MAX_MATRIX_ORIGINS {
+        Err(PathError::TooLarge(Custom(Status::UnprocessableEntity, "More than 50 origins")))
+    } else if request.targets.len() > MAX_MATRIX_TARGETS {
+        Err(PathError::TooLarge(Custom(Status::UnprocessableEntity, "More than 500 targets")))
+    } else {
+        let begin = Instant::now();
+        let limits = limits.lower(request.limits).to_limits(begin);
+        let results = pathfinder::matrix::cost_matrix(nav_grid, &request.origins, &request.targets, &request.game_state, &limits);
+        let duration = Instant::now() - begin;
+        println!("[Matrix] {}x{} in {:.2}ms, {} visited", request.origins.len(), request.targets.len(), duration.as_secs_f64() * 1000f64,
+                 results.iter().map(|result| result.visited).sum::<usize>());
+        let stopped = results.iter().map(|result| result.stopped).collect();
+        Ok(Json(MatrixResponse { costs: results.into_iter().map(|result| result.costs).collect(), stopped }))
     }
 }
 
@@ -65,38 +424,89 @@ fn handle_select_request(data_selection: &State<DataSelection>) -> Json<DataSele
 fn rocket() -> Rocket<Build> {
     let options = Options::parse();
     let nav_grid = load_nav_grid(&options.navgrid).or_exit_e_("Error loading NavGrid");
+    let profiles = match &options.profiles {
+        Some(path) => load_profiles(path).or_exit_e_("Error loading cost profiles"),
+        None => Profiles::default(),
+    };
     let mut data_selection = DataSelection::default();
-    nav_grid.iter_edges().flat_map(|e| &e.requirements).for_each(|r| {
+    nav_grid.iter_edges().flat_map(|e| &e.requirements).for_each(|r| r.walk(&mut |r| {
         match r {
             RequirementDefinition::Varp { index, .. } => data_selection.varps.insert(*index),
             RequirementDefinition::Varbit { index, .. } => data_selection.varbits.insert(*index),
-            RequirementDefinition::Item { item, .. } => data_selection.items.insert(item.to_string()),
-            RequirementDefinition::Skill { skill, .. } => data_selection.skills.insert(skill.clone()),
+            RequirementDefinition::Item { item, container, .. } => {
+                data_selection.bank |= container.includes_bank();
+                data_selection.items.insert(item.to_string())
+            }
+            RequirementDefinition::Skill { skill, .. } => data_selection.skills.insert(*skill),
+            RequirementDefinition::CombatLevel { .. } => {
+                data_selection.skills.extend(Skill::COMBAT);
+                true
+            }
+            RequirementDefinition::TotalLevel { .. } => {
+                data_selection.skills.extend(Skill::ALL);
+                true
+            }
+            RequirementDefinition::QuestPoints { .. } => data_selection.varps.insert(QUEST_POINTS_VARP),
+            RequirementDefinition::Quest { definition: Some(definition), .. } => match definition.var {
+                QuestVar::Varp(index) => data_selection.varps.insert(index),
+                QuestVar::Varbit(index) => data_selection.varbits.insert(index),
+            },
             _ => false
         };
-    });
+    }));
     let prometheus = PrometheusMetrics::new();
     rocket::build()
         .attach(prometheus.clone())
         .mount("/metrics", prometheus)
         .mount("/path", routes![handle_path_request])
+        .mount("/route", routes![handle_route_request])
+        .mount("/alternatives", routes![handle_alternatives_request])
+        .mount("/matrix", routes![handle_matrix_request])
+        .mount("/plan", routes![handle_plan_request])
         .mount("/select", routes![handle_select_request])
         .manage(nav_grid)
         .manage(data_selection)
+        .manage(profiles)
+        .manage(options.limits)
 }
 
-fn load_nav_grid(path: impl AsRef<Path>) -> Result<NavGrid, ciborium::de::Error<std::io::Error>> {
-    let file = File::open(path)?;
-    let decoder = GzDecoder::new(file);
-    let mut reader = BufReader::new(decoder);
-    let mut nav_grid = NavGrid::new();
-    for vertex in &mut nav_grid.vertices {
-        let mut buf = [0; 2];
-        reader.read_exact(&mut buf)?;
-        vertex.flags = buf[0];
-        vertex.extra_edges_and_group = buf[1];
-    }
-    nav_grid.edges = ciborium::de::from_reader(&mut reader)?;
-    nav_grid.teleports = ciborium::de::from_reader(&mut reader)?;
+fn load_profiles(path: impl AsRef<Path>) -> Result<Profiles, std::io::Error> {
+    let profiles: HashMap<String, CostProfile> = serde_json::from_reader(std::io::BufReader::new(std::fs::File::open(path)?))?;
+    println!("Loaded {} cost profiles", profiles.len());
+    Ok(Profiles(profiles))
+}
+
+fn load_nav_grid(path: impl AsRef<Path>) -> Result<NavGrid, FormatError> {
+    let (header, nav_grid) = model::format::open_nav_grid(path)?;
+    println!("Loaded NavGrid revision {}, generated at {}, {} edges, {} teleports, {} landmarks, {}", header.revision, header.timestamp,
+             header.edge_count, header.teleport_count, nav_grid.landmarks.len(), if nav_grid.vertices.is_mapped() { "memory mapped" } else { "decompressed" });
     Ok(nav_grid)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan_request(stops: usize) -> Json<PlanRequest> {
+        let start = Coordinate { x: 3200, y: 3200, plane: 0 };
+        Json(PlanRequest {
+            start,
+            stops: vec![start; stops],
+            end: None,
+            game_state: GameState::default(),
+            limits: RequestLimits::default(),
+            compress: None,
+            compression: Waypoints::default(),
+        })
+    }
+
+    #[test]
+    fn too_many_stops_are_rejected() {
+        let nav_grid = NavGrid::new();
+        let limits = RequestLimits::default();
+        let response = handle_plan_request(plan_request(MAX_PLAN_STOPS + 1), State::from(&nav_grid), State::from(&limits));
+        assert!(matches!(response, Err(PathError::TooLarge(_))));
+        let response = handle_plan_request(plan_request(MAX_PLAN_STOPS), State::from(&nav_grid), State::from(&limits));
+        assert!(response.is_ok());
+    }
+}